@@ -12,7 +12,7 @@ fn bench_slide_from(c: &mut Criterion) {
             raxrdxal(
                 Xoshiro256StarStar::from_rng(&mut rng).unwrap(),
                 shape,
-                |rng, shape| BoxPuzzle::<u8>::random_with_rng(rng, shape),
+                BoxPuzzle::<u8>::random_with_rng,
             ),
         );
 
@@ -21,7 +21,7 @@ fn bench_slide_from(c: &mut Criterion) {
             raxrdxal(
                 Xoshiro256StarStar::from_rng(&mut rng).unwrap(),
                 shape,
-                |rng, shape| NdArrayPuzzle::<u8>::random_with_rng(rng, shape),
+                NdArrayPuzzle::<u8>::random_with_rng,
             ),
         );
     }
@@ -0,0 +1,330 @@
+use core::ops::Range;
+use core::time::Duration;
+
+use crate::{AnyPuzzle, Player, Puzzle, Replay, ReplayError, Solver};
+
+/// Where the time of a recorded solve went.
+///
+/// Times count from the first move, so that whatever happened before it (inspection, mostly)
+/// does not weigh on the first phase.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub phases: Vec<Phase>,
+    pub pauses: Vec<Pause>,
+    /// Tiles moved, i.e. the move count in the single-tile metric.
+    pub moves: usize,
+    pub duration: Duration,
+    // when every move happened, and how many tiles it moved
+    timeline: Vec<(Duration, usize)>,
+}
+
+/// A stretch of the solve ending when more of the board is done for good: rows from the top
+/// and columns from the left, as reduction methods go, and finally the whole board.
+///
+/// Boards that wrap around, or with walls or several blanks, are not reduced that way, so their
+/// only phase is the whole solve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    /// Rows done from the top by the end of the phase.
+    pub rows: usize,
+    /// Columns done from the left by the end of the phase.
+    pub columns: usize,
+    /// Indices of the moves of this phase in [`Replay::moves`].
+    pub moves: Range<usize>,
+    /// Tiles moved during the phase.
+    pub tiles: usize,
+    pub start: Duration,
+    pub end: Duration,
+}
+
+impl Phase {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// Tiles moved per second, or `None` if the phase took no time at all.
+    pub fn tps(&self) -> Option<f64> {
+        tps(self.tiles, self.duration())
+    }
+}
+
+/// Time without moving, longer than the threshold given to [`Analysis::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pause {
+    /// Index of the move the pause came before in [`Replay::moves`].
+    pub before: usize,
+    pub start: Duration,
+    pub length: Duration,
+}
+
+/// How a solve compares to a reference solution found by [`Solver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Efficiency {
+    /// Tiles moved in the solve.
+    pub moves: usize,
+    /// Length of the reference solution.
+    pub reference: usize,
+    /// Whether the reference is one of the shortest solutions.
+    pub optimal: bool,
+}
+
+impl Efficiency {
+    /// Plays `replay` back and solves its starting board, optimally if `weight` is `1` and at
+    /// most `weight` times as long as optimal otherwise. See [`Solver::solve_weighted`].
+    ///
+    /// Fails with [`ReplayError::Unsolvable`] if there is no reference to compare to, and with
+    /// [`ReplayError::Unsupported`] on boards the solver does not handle: with walls or several
+    /// blanks.
+    pub fn of(replay: &Replay, weight: usize) -> Result<Self, ReplayError> {
+        let solver = match Player::new(replay)?.puzzle() {
+            AnyPuzzle::Plain(start) => Solver::new(start),
+            AnyPuzzle::Torus(start) => Solver::new_torus(start),
+            _ => return Err(ReplayError::Unsupported),
+        };
+        let moves = Analysis::new(replay, Duration::MAX)?.moves;
+
+        let reference = solver
+            .solve_weighted(weight)
+            .ok_or(ReplayError::Unsolvable)?;
+
+        Ok(Self {
+            moves,
+            reference: reference.len(),
+            optimal: weight == 1,
+        })
+    }
+
+    /// How many times longer than the reference the solve was.
+    pub fn ratio(&self) -> f64 {
+        self.moves as f64 / self.reference as f64
+    }
+}
+
+impl Analysis {
+    /// Plays `replay` back, splitting it into phases and finding pauses longer than `pause`.
+    ///
+    /// Unfinished solves can be analysed as well; they just have no phase for the last part.
+    pub fn new(replay: &Replay, pause: Duration) -> Result<Self, ReplayError> {
+        let mut player = Player::new(replay)?;
+
+        let mut timeline = Vec::with_capacity(replay.moves.len());
+        let mut progress = Vec::with_capacity(replay.moves.len());
+        while let Some(step) = player.step() {
+            let step = step?;
+            timeline.push((Duration::from_millis(step.time), step.count));
+            progress.push(done(player.puzzle()));
+        }
+
+        let origin = timeline.first().map_or(Duration::ZERO, |&(time, _)| time);
+        for (time, _) in &mut timeline {
+            *time -= origin;
+        }
+
+        let pauses = timeline
+            .windows(2)
+            .enumerate()
+            .filter_map(|(idx, pair)| {
+                let length = pair[1].0 - pair[0].0;
+                (length > pause).then_some(Pause {
+                    before: idx + 1,
+                    start: pair[0].0,
+                    length,
+                })
+            })
+            .collect();
+
+        // a phase ends at the move after which that much of the board stays done until the end
+        let mut phases = Vec::new();
+        let mut kept = (usize::MAX, usize::MAX);
+        let mut ends = Vec::new();
+        for (idx, &(rows, columns)) in progress.iter().enumerate().rev() {
+            kept = (kept.0.min(rows), kept.1.min(columns));
+            ends.push((idx, kept));
+        }
+        ends.reverse();
+
+        let mut previous = (0, 0);
+        let mut first = 0;
+        for (idx, kept) in ends {
+            if kept.0 + kept.1 <= previous.0 + previous.1 {
+                continue;
+            }
+
+            phases.push(Phase {
+                rows: kept.0,
+                columns: kept.1,
+                moves: first..idx + 1,
+                tiles: timeline[first..=idx].iter().map(|&(_, count)| count).sum(),
+                start: match first {
+                    0 => Duration::ZERO,
+                    first => timeline[first - 1].0,
+                },
+                end: timeline[idx].0,
+            });
+            previous = kept;
+            first = idx + 1;
+        }
+
+        Ok(Self {
+            phases,
+            pauses,
+            moves: timeline.iter().map(|&(_, count)| count).sum(),
+            duration: timeline.last().map_or(Duration::ZERO, |&(time, _)| time),
+            timeline,
+        })
+    }
+
+    /// Tiles moved per second over the whole solve, or `None` if it took no time at all.
+    pub fn tps(&self) -> Option<f64> {
+        tps(self.moves, self.duration)
+    }
+
+    /// Tiles moved per second in every consecutive `interval` of the solve, for graphing.
+    pub fn tps_graph(&self, interval: Duration) -> Vec<f64> {
+        assert!(!interval.is_zero(), "interval must not be zero");
+
+        let buckets = (self.duration.as_nanos() / interval.as_nanos()) as usize + 1;
+        let mut tiles = vec![0; buckets];
+        for &(time, count) in &self.timeline {
+            tiles[(time.as_nanos() / interval.as_nanos()) as usize] += count;
+        }
+
+        tiles
+            .into_iter()
+            .map(|tiles| tiles as f64 / interval.as_secs_f64())
+            .collect()
+    }
+}
+
+// how many rows from the top, then columns from the left, are done, reducing the board the way
+// people do until only the last 2x2 is left, and the whole board once it is solved
+fn done(puzzle: &AnyPuzzle) -> (usize, usize) {
+    let (width, height) = puzzle.shape();
+    if puzzle.is_solved() {
+        return (height, width);
+    }
+    let AnyPuzzle::Plain(puzzle) = puzzle else {
+        return (0, 0);
+    };
+
+    let solved = |x: usize, y: usize| puzzle[(x, y)] == y * width + x + 1;
+    let (mut rows, mut columns) = (0, 0);
+    loop {
+        if rows + 2 < height && (columns..width).all(|x| solved(x, rows)) {
+            rows += 1;
+        } else if columns + 2 < width && (rows..height).all(|y| solved(columns, y)) {
+            columns += 1;
+        } else {
+            return (rows, columns);
+        }
+    }
+}
+
+fn tps(tiles: usize, duration: Duration) -> Option<f64> {
+    (!duration.is_zero()).then(|| tiles as f64 / duration.as_secs_f64())
+}
+
+#[test]
+fn analysis_works() {
+    use crate::{BoxPuzzle, Direction::*, Recorder, TorusPuzzle, WalledPuzzle};
+
+    // 4 1 2
+    // 7 5 3
+    //   8 6
+    let scramble = BoxPuzzle::from_pieces((3, 3), vec![4, 1, 2, 7, 5, 3, 0, 8, 6]).unwrap();
+    let mut recorder = Recorder::new(scramble);
+    for (from, at) in [((0, 0), 1000), ((2, 0), 1500), ((2, 2), 4000)] {
+        recorder.slide_from_at(from, Duration::from_millis(at));
+    }
+    let replay = recorder.into_replay();
+
+    let analysis = Analysis::new(&replay, Duration::from_secs(1)).unwrap();
+    assert_eq!(analysis.moves, 6);
+    assert_eq!(analysis.duration, Duration::from_secs(3));
+    assert_eq!(analysis.tps(), Some(2.0));
+    assert_eq!(
+        analysis.phases,
+        [
+            Phase {
+                rows: 0,
+                columns: 1,
+                moves: 0..2,
+                tiles: 4,
+                start: Duration::ZERO,
+                end: Duration::from_millis(500),
+            },
+            Phase {
+                rows: 3,
+                columns: 3,
+                moves: 2..3,
+                tiles: 2,
+                start: Duration::from_millis(500),
+                end: Duration::from_secs(3),
+            }
+        ]
+    );
+    assert_eq!(analysis.phases[0].tps(), Some(8.0));
+    assert_eq!(
+        analysis.pauses,
+        [Pause {
+            before: 2,
+            start: Duration::from_millis(500),
+            length: Duration::from_millis(2500),
+        }]
+    );
+    assert_eq!(
+        analysis.tps_graph(Duration::from_secs(1)),
+        [4.0, 0.0, 0.0, 2.0]
+    );
+
+    let efficiency = Efficiency::of(&replay, 1).unwrap();
+    assert_eq!(efficiency.reference, 6);
+    assert_eq!(efficiency.ratio(), 1.0);
+
+    // a column done and then broken again is not a phase of its own
+    let mut recorder = Recorder::new(BoxPuzzle::from_pieces((3, 3), replay.board).unwrap());
+    for from in [(0, 0), (2, 0), (0, 0), (2, 0), (2, 2)] {
+        recorder.slide_from_at(from, Duration::ZERO);
+    }
+    let replay = recorder.into_replay();
+    let analysis = Analysis::new(&replay, Duration::from_secs(1)).unwrap();
+    assert_eq!(analysis.phases.len(), 2);
+    assert_eq!(analysis.phases[0].moves, 0..4);
+    assert_eq!(analysis.phases[0].tps(), None);
+    assert_eq!(analysis.tps(), None);
+    assert_eq!(
+        Efficiency::of(&replay, 2).map(|efficiency| efficiency.moves),
+        Ok(10)
+    );
+
+    // a single phase on a board with walls, which the solver does not take
+    let mut recorder = Recorder::new(WalledPuzzle::<u8>::solved((3, 3), [(1, 1)]));
+    recorder.slide_from_at((2, 0), Duration::from_millis(500));
+    recorder.slide_from_at((2, 2), Duration::from_millis(1500));
+    let replay = recorder.into_replay();
+    let analysis = Analysis::new(&replay, Duration::MAX).unwrap();
+    assert_eq!(analysis.moves, 4);
+    assert_eq!(analysis.phases.len(), 1);
+    assert_eq!(analysis.phases[0].moves, 0..2);
+    assert_eq!(Efficiency::of(&replay, 1), Err(ReplayError::Unsupported));
+
+    // round a torus, the short way back
+    let mut recorder = Recorder::new(TorusPuzzle::<u8>::solved((4, 3)));
+    recorder.slide_towards_at(Left, 1, Duration::from_millis(500));
+    recorder.slide_towards_at(Right, 1, Duration::from_millis(1000));
+    let replay = recorder.into_replay();
+    assert_eq!(replay.verify(), Ok(()));
+    let analysis = Analysis::new(&replay, Duration::MAX).unwrap();
+    assert_eq!(analysis.moves, 2);
+    assert_eq!(analysis.phases.len(), 1);
+    assert_eq!(analysis.tps(), Some(4.0));
+    assert_eq!(
+        Efficiency::of(&replay, 1).map(|efficiency| efficiency.reference),
+        Ok(0)
+    );
+
+    // no reference for a board that cannot be solved
+    let unsolvable = BoxPuzzle::from_pieces((3, 3), vec![2, 1, 3, 4, 5, 6, 7, 8, 0]).unwrap();
+    let replay = Recorder::new(unsolvable).into_replay();
+    assert_eq!(Efficiency::of(&replay, 1), Err(ReplayError::Unsolvable));
+}
@@ -0,0 +1,455 @@
+use core::time::Duration;
+use std::io::{self, Write};
+
+use crate::replay::offset;
+use crate::{
+    AnyPuzzle, BoxPuzzle, Direction, Move, Palette, Piece, Player, Puzzle, Replay, ReplayError,
+    Scheme,
+};
+
+// 3x5 digits, row by row from the top, most significant bit first
+const DIGITS: [u16; 10] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_001_001_001,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+];
+
+// colour indices of a `Clip`; tiles come after these
+const BACKGROUND: u8 = 0;
+const TEXT: u8 = 1;
+
+/// Turns a solve into an animated [`Clip`], rasterized in software.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Animation {
+    /// Side of a tile, in pixels.
+    pub tile_size: u32,
+    /// Space between tiles and around the board, in pixels.
+    pub gap: u32,
+    pub scheme: Scheme,
+    pub palette: Palette,
+    /// Frames drawn for every move, the last one being the board after it; `1` means no
+    /// tweening.
+    pub tweens: usize,
+    /// How long tiles take to slide when tweening, at most.
+    pub slide: Duration,
+    /// How long the first and last boards stay on screen.
+    pub hold: Duration,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            tile_size: 48,
+            gap: 4,
+            scheme: Scheme::default(),
+            palette: Palette::default(),
+            tweens: 1,
+            slide: Duration::from_millis(80),
+            hold: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Indexed frames, ready to be written as a GIF or an APNG.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clip {
+    pub width: u32,
+    pub height: u32,
+    /// What the pixels of every frame index into.
+    pub colors: Vec<crate::Rgb>,
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// One colour index per pixel, row by row.
+    pub pixels: Vec<u8>,
+    /// How long the frame stays on screen.
+    pub delay: Duration,
+}
+
+impl Animation {
+    /// Animates a replay, moves happening when they did.
+    pub fn replay(&self, replay: &Replay) -> Result<Clip, ReplayError> {
+        let mut player = Player::new(replay)?;
+        let start = player.puzzle().clone();
+
+        let mut steps = Vec::with_capacity(replay.moves.len());
+        let mut previous = None;
+        while let Some(step) = player.step() {
+            let step = step?;
+            let time = Duration::from_millis(step.time);
+            // the wait before the first move is inspection, which nobody wants to watch
+            let wait = previous.map_or(self.slide, |previous| time - previous);
+            steps.push((*step, wait));
+            previous = Some(time);
+        }
+
+        Ok(self.animate(start, steps))
+    }
+
+    /// Animates `moves`, in the sense of [`Puzzle::slide_towards`], at a fixed number of moves
+    /// per second. Returns `None` if a move cannot be made, or if `tps` is not a positive finite
+    /// number.
+    pub fn moves<T: Piece, P: Puzzle<T>>(
+        &self,
+        board: &P,
+        moves: &[Direction],
+        tps: f64,
+    ) -> Option<Clip> {
+        let pieces = board
+            .iter()
+            .cloned()
+            .map(num::cast)
+            .collect::<Option<Vec<usize>>>()
+            .expect("could not cast pieces to usize");
+        let start = BoxPuzzle::from_pieces(board.shape(), pieces).ok()?;
+
+        let wait = Duration::try_from_secs_f64(1.0 / tps)
+            .ok()
+            .filter(|wait| !wait.is_zero())?;
+        let mut puzzle = start.clone();
+        let mut steps = Vec::with_capacity(moves.len());
+        for &direction in moves {
+            let (dx, dy): (isize, isize) = direction.into();
+            let (x, y) = puzzle.index_of(0)?;
+            let from = (x.checked_add_signed(-dx)?, y.checked_add_signed(-dy)?);
+            if puzzle.slide_from(from) != Some(1) {
+                return None;
+            }
+            let step = Move {
+                position: from,
+                direction,
+                count: 1,
+                time: 0,
+            };
+            steps.push((step, wait));
+        }
+
+        Some(self.animate(AnyPuzzle::Plain(start), steps))
+    }
+
+    fn animate(
+        &self,
+        mut puzzle: AnyPuzzle,
+        steps: impl IntoIterator<Item = (Move, Duration)>,
+    ) -> Clip {
+        let (width, height) = self.board_size(puzzle.shape());
+        let mut colors = vec![self.palette.background, self.palette.text];
+        colors.extend(self.palette.tiles().iter().take(254));
+        let mut clip = Clip {
+            width,
+            height,
+            colors,
+            frames: Vec::new(),
+        };
+
+        clip.push(self.draw(&puzzle, None));
+        clip.wait(self.hold);
+
+        for (step, wait) in steps {
+            let slide = match self.tweens {
+                0 | 1 => Duration::ZERO,
+                _ => wait.min(self.slide),
+            };
+            clip.wait(wait - slide);
+
+            for tween in 1..self.tweens.max(1) {
+                let sliding = Sliding {
+                    step,
+                    progress: (tween, self.tweens),
+                };
+                clip.push(self.draw(&puzzle, Some(sliding)));
+                clip.wait(slide / self.tweens as u32);
+            }
+
+            puzzle.play(&step);
+            clip.push(self.draw(&puzzle, None));
+            clip.wait(slide / self.tweens.max(1) as u32);
+        }
+
+        clip.wait(self.hold);
+        clip
+    }
+
+    fn board_size(&self, (width, height): (usize, usize)) -> (u32, u32) {
+        let size = |tiles: usize| tiles as u32 * (self.tile_size + self.gap) + self.gap;
+        (size(width), size(height))
+    }
+
+    fn draw(&self, puzzle: &AnyPuzzle, sliding: Option<Sliding>) -> Vec<u8> {
+        let shape = puzzle.shape();
+        let (width, height) = self.board_size(shape);
+        let mut canvas = Canvas {
+            width,
+            height,
+            pixels: vec![BACKGROUND; (width * height) as usize],
+        };
+
+        let pitch = (self.tile_size + self.gap) as i64;
+        let digits = ((shape.0 * shape.1 - 1) as f32).log10() as u32 + 1;
+        let scale = (self.tile_size * 2 / 25)
+            .min(self.tile_size * 3 / 4 / (4 * digits - 1))
+            .max(1);
+
+        for ((x, y), &piece) in puzzle.iter_indexed() {
+            if piece == 0 {
+                continue;
+            }
+
+            let mut left = self.gap as i64 + x as i64 * pitch;
+            let mut top = self.gap as i64 + y as i64 * pitch;
+            if let Some(Sliding {
+                step,
+                progress: (done, of),
+            }) = sliding
+            {
+                let moving = (0..step.count)
+                    .any(|count| offset(step.position, step.direction, count, shape) == (x, y));
+                if moving {
+                    let (dx, dy): (isize, isize) = step.direction.into();
+                    left += dx as i64 * pitch * done as i64 / of as i64;
+                    top += dy as i64 * pitch * done as i64 / of as i64;
+                }
+            }
+
+            let goal = ((piece - 1) % shape.0, (piece - 1) / shape.0);
+            let class = self.scheme.class(goal, shape) % self.palette.tiles().len().min(254);
+            let size = self.tile_size as i64;
+            canvas.fill(left, top, size, size, 2 + class as u8);
+            canvas.number(left + size / 2, top + size / 2, piece, scale as i64);
+        }
+
+        canvas.pixels
+    }
+}
+
+impl Clip {
+    fn push(&mut self, pixels: Vec<u8>) {
+        // frames that would never be seen
+        if self.frames.last().is_some_and(|last| last.delay.is_zero()) {
+            self.frames.pop();
+        }
+        self.frames.push(Frame {
+            pixels,
+            delay: Duration::ZERO,
+        });
+    }
+
+    fn wait(&mut self, time: Duration) {
+        if let Some(last) = self.frames.last_mut() {
+            last.delay += time;
+        }
+    }
+
+    /// Writes an endlessly looping GIF, which fails for clips wider or taller than the 65535
+    /// pixels a GIF can be.
+    pub fn write_gif(&self, out: impl Write) -> io::Result<()> {
+        let palette = self
+            .colors
+            .iter()
+            .flat_map(|color| [color.0, color.1, color.2])
+            .collect::<Vec<_>>();
+        let size = |pixels: u32| {
+            u16::try_from(pixels).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{pixels} pixels is too large for a GIF"),
+                )
+            })
+        };
+        let (width, height) = (size(self.width)?, size(self.height)?);
+
+        let mut encoder =
+            gif::Encoder::new(out, width, height, &palette).map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+
+        // GIF delays are in hundredths of a second; rounding the running total keeps the
+        // rounding errors from adding up
+        let (mut elapsed, mut shown) = (Duration::ZERO, 0);
+        for frame in &self.frames {
+            elapsed += frame.delay;
+            let until = (elapsed.as_millis() as u64 + 5) / 10;
+
+            let mut gif_frame =
+                gif::Frame::from_indexed_pixels(width, height, frame.pixels.clone(), None);
+            gif_frame.delay = (until - shown).min(u16::MAX as u64) as u16;
+            shown = until;
+
+            encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes an endlessly looping APNG.
+    pub fn write_apng(&self, out: impl Write) -> io::Result<()> {
+        let palette = self
+            .colors
+            .iter()
+            .flat_map(|color| [color.0, color.1, color.2])
+            .collect::<Vec<_>>();
+
+        let mut encoder = png::Encoder::new(out, self.width, self.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(palette);
+        encoder
+            .set_animated(self.frames.len() as u32, 0)
+            .map_err(io::Error::other)?;
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        for frame in &self.frames {
+            let millis = frame.delay.as_millis().min(u16::MAX as u128) as u16;
+            writer
+                .set_frame_delay(millis, 1000)
+                .map_err(io::Error::other)?;
+            writer
+                .write_image_data(&frame.pixels)
+                .map_err(io::Error::other)?;
+        }
+
+        writer.finish().map_err(io::Error::other)
+    }
+}
+
+// the tiles of `step`, `progress.0 / progress.1` of the way to where they go
+#[derive(Clone, Copy)]
+struct Sliding {
+    step: Move,
+    progress: (usize, usize),
+}
+
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn fill(&mut self, left: i64, top: i64, width: i64, height: i64, color: u8) {
+        let columns = left.max(0)..(left + width).min(self.width as i64);
+        for y in top.max(0)..(top + height).min(self.height as i64) {
+            let row = (y * self.width as i64) as usize;
+            for x in columns.clone() {
+                self.pixels[row + x as usize] = color;
+            }
+        }
+    }
+
+    // centred on (x, y)
+    fn number(&mut self, x: i64, y: i64, number: usize, scale: i64) {
+        let digits = number.to_string();
+        let width = (digits.len() as i64 * 4 - 1) * scale;
+        let (left, top) = (x - width / 2, y - 5 * scale / 2);
+
+        for (idx, digit) in digits.bytes().enumerate() {
+            let glyph = DIGITS[(digit - b'0') as usize];
+            let left = left + idx as i64 * 4 * scale;
+            for bit in 0..15 {
+                if glyph & (1 << (14 - bit)) != 0 {
+                    let (column, row) = (bit % 3, bit / 3);
+                    self.fill(left + column * scale, top + row * scale, scale, scale, TEXT);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn animation_works() {
+    use crate::{Direction::*, Recorder, Rgb};
+
+    let board = BoxPuzzle::<u8>::default();
+    let animation = Animation::default();
+
+    let clip = animation
+        .moves(&board, &[Down, Right, Left, Up], 4.0)
+        .unwrap();
+    assert_eq!((clip.width, clip.height), (212, 212));
+    assert_eq!(clip.frames.len(), 5);
+    assert_eq!(clip.frames[0].delay, Duration::from_millis(1250));
+    assert_eq!(clip.frames[1].delay, Duration::from_millis(250));
+    assert_eq!(clip.frames[4].delay, Duration::from_secs(1));
+    assert_eq!(
+        clip.frames[0],
+        Frame {
+            delay: clip.frames[4].delay + Duration::from_millis(250),
+            ..clip.frames[4].clone()
+        }
+    );
+    assert_ne!(clip.frames[0].pixels, clip.frames[1].pixels);
+    assert_eq!(animation.moves(&board, &[Up], 4.0), None);
+    for tps in [0.0, -4.0, f64::NAN, f64::INFINITY] {
+        assert_eq!(animation.moves(&board, &[Down], tps), None);
+    }
+    assert_eq!(Palette::new(Rgb(0, 0, 0), Rgb(0, 0, 0), vec![]), None);
+
+    // a tile with a 1 on it, and the text colour inside it
+    let tile = |frame: &Frame, (x, y): (usize, usize)| {
+        let (left, top) = (4 + x * 52, 4 + y * 52);
+        (top..top + 48)
+            .flat_map(move |y| (left..left + 48).map(move |x| (x, y)))
+            .map(|(x, y)| frame.pixels[y * 212 + x])
+            .collect::<Vec<_>>()
+    };
+    assert!(tile(&clip.frames[0], (0, 0)).contains(&TEXT));
+    assert!(tile(&clip.frames[0], (0, 0)).contains(&2));
+    assert!(!tile(&clip.frames[0], (3, 3)).contains(&TEXT));
+
+    // halfway through sliding down, the tile straddles both cells
+    let mut recorder = Recorder::new(board);
+    recorder.slide_from_at((3, 2), Duration::from_millis(2000));
+    recorder.slide_from_at((3, 3), Duration::from_millis(2500));
+    let tweened = Animation {
+        tweens: 2,
+        ..animation.clone()
+    }
+    .replay(&recorder.into_replay())
+    .unwrap();
+    let delays = tweened
+        .frames
+        .iter()
+        .map(|frame| frame.delay)
+        .collect::<Vec<_>>();
+    assert_eq!(delays, [1000, 40, 460, 40, 1040].map(Duration::from_millis));
+    let halfway = &tweened.frames[1];
+    assert!(tile(halfway, (3, 2))[..24 * 48]
+        .iter()
+        .all(|&pixel| pixel == BACKGROUND));
+    assert!(tile(halfway, (3, 3))[24 * 48..]
+        .iter()
+        .all(|&pixel| pixel == BACKGROUND));
+    assert!(tile(halfway, (3, 2))[24 * 48..].contains(&2));
+
+    let mut gif = Vec::new();
+    clip.write_gif(&mut gif).unwrap();
+    assert!(gif.starts_with(b"GIF89a"));
+    let wide = Clip {
+        width: 70_000,
+        ..clip.clone()
+    };
+    let error = wide.write_gif(Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    let mut decoder = gif::DecodeOptions::new().read_info(&gif[..]).unwrap();
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    assert_eq!(delays, [125, 25, 25, 25, 100]);
+
+    let mut apng = Vec::new();
+    tweened.write_apng(&mut apng).unwrap();
+    let decoder = png::Decoder::new(&apng[..]);
+    let reader = decoder.read_info().unwrap();
+    assert_eq!(reader.info().animation_control().unwrap().num_frames, 5);
+}
@@ -0,0 +1,267 @@
+use core::fmt::Write;
+
+use crate::{Palette, Piece, Puzzle, Rgb, Scheme};
+
+/// How many colours a terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Colors {
+    /// No escape sequences at all.
+    Plain,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl Colors {
+    /// Guesses from `NO_COLOR`, `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::from_env(
+            var("NO_COLOR").as_deref(),
+            var("COLORTERM").as_deref(),
+            var("TERM").as_deref(),
+        )
+    }
+
+    /// [`detect`](Self::detect), with the variables given.
+    pub fn from_env(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        // https://no-color.org: set and not empty
+        if no_color.is_some_and(|no_color| !no_color.is_empty()) {
+            return Colors::Plain;
+        }
+
+        match (colorterm, term) {
+            (Some("truecolor" | "24bit"), _) => Colors::TrueColor,
+            (_, None | Some("" | "dumb")) => Colors::Plain,
+            (_, Some(term)) if term.ends_with("-direct") => Colors::TrueColor,
+            (_, Some(term)) if term.contains("256color") => Colors::Ansi256,
+            _ => Colors::Ansi16,
+        }
+    }
+}
+
+/// Renders puzzles for terminals, coloured with ANSI escape sequences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ansi {
+    pub colors: Colors,
+    pub scheme: Scheme,
+    pub palette: Palette,
+    /// Colour of tiles already where they belong, if they should stand out that way.
+    pub solved: Option<Rgb>,
+}
+
+impl Default for Ansi {
+    fn default() -> Self {
+        Self {
+            colors: Colors::detect(),
+            scheme: Scheme::default(),
+            palette: Palette::default(),
+            solved: None,
+        }
+    }
+}
+
+impl Ansi {
+    /// Renders a board, one line per row. Without colours, this is the same as the `Display`
+    /// impls of the puzzles.
+    pub fn render<T: Piece, P: Puzzle<T>>(&self, puzzle: &P) -> String {
+        let shape = puzzle.shape();
+        let digits = ((shape.0 * shape.1 - 1) as f32).log10() as usize + 1;
+
+        let mut out = String::new();
+        for ((x, y), piece) in puzzle.iter_indexed() {
+            let piece: usize = num::cast(piece.clone()).expect("could not cast piece to usize");
+
+            if self.colors == Colors::Plain {
+                match piece {
+                    0 => out += &" ".repeat(digits + 1),
+                    piece => {
+                        let _ = write!(out, "{piece: >digits$} ");
+                    }
+                }
+            } else {
+                // one column of padding on both sides, so that tiles do not run into each other
+                match piece {
+                    0 => out += &" ".repeat(digits + 2),
+                    piece => {
+                        let goal = ((piece - 1) % shape.0, (piece - 1) / shape.0);
+                        let background = match self.solved {
+                            Some(solved) if goal == (x, y) => solved,
+                            _ => self.palette.tile(self.scheme.class(goal, shape)),
+                        };
+                        let _ = write!(
+                            out,
+                            "\x1b[{};{}m {piece: >digits$} \x1b[0m",
+                            self.sgr(self.palette.text, false),
+                            self.sgr(background, true),
+                        );
+                    }
+                }
+            }
+
+            if x + 1 == shape.0 {
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+
+    // the SGR parameters setting the foreground or background to the closest `color` available
+    fn sgr(&self, color: Rgb, background: bool) -> String {
+        let Rgb(r, g, b) = color;
+        match (self.colors, background) {
+            (Colors::TrueColor, false) => format!("38;2;{r};{g};{b}"),
+            (Colors::TrueColor, true) => format!("48;2;{r};{g};{b}"),
+            (Colors::Ansi256, false) => format!("38;5;{}", ansi256(color)),
+            (Colors::Ansi256, true) => format!("48;5;{}", ansi256(color)),
+            (_, background) => {
+                let idx = ansi16(color);
+                let base = match (idx < 8, background) {
+                    (true, false) => 30,
+                    (false, false) => 90 - 8,
+                    (true, true) => 40,
+                    (false, true) => 100 - 8,
+                };
+                (base + idx).to_string()
+            }
+        }
+    }
+}
+
+// xterm's defaults
+const ANSI16: [Rgb; 16] = [
+    Rgb(0x00, 0x00, 0x00),
+    Rgb(0xcd, 0x00, 0x00),
+    Rgb(0x00, 0xcd, 0x00),
+    Rgb(0xcd, 0xcd, 0x00),
+    Rgb(0x00, 0x00, 0xee),
+    Rgb(0xcd, 0x00, 0xcd),
+    Rgb(0x00, 0xcd, 0xcd),
+    Rgb(0xe5, 0xe5, 0xe5),
+    Rgb(0x7f, 0x7f, 0x7f),
+    Rgb(0xff, 0x00, 0x00),
+    Rgb(0x00, 0xff, 0x00),
+    Rgb(0xff, 0xff, 0x00),
+    Rgb(0x5c, 0x5c, 0xff),
+    Rgb(0xff, 0x00, 0xff),
+    Rgb(0x00, 0xff, 0xff),
+    Rgb(0xff, 0xff, 0xff),
+];
+
+fn distance(Rgb(r1, g1, b1): Rgb, Rgb(r2, g2, b2): Rgb) -> u32 {
+    let square = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    square(r1, r2) + square(g1, g2) + square(b1, b2)
+}
+
+fn ansi16(color: Rgb) -> u8 {
+    (0..16)
+        .min_by_key(|&idx| distance(color, ANSI16[idx as usize]))
+        .expect("potential BUG: no colours to choose from")
+}
+
+// the closest of the 6x6x6 cube and the greyscale ramp
+fn ansi256(color: Rgb) -> u8 {
+    const LEVELS: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+    let level = |c: u8| {
+        (0..6)
+            .min_by_key(|&idx| (LEVELS[idx] as i32 - c as i32).abs())
+            .expect("potential BUG: no levels to choose from")
+    };
+    let (r, g, b) = (level(color.0), level(color.1), level(color.2));
+    let cube = Rgb(LEVELS[r], LEVELS[g], LEVELS[b]);
+
+    let mean = (color.0 as u32 + color.1 as u32 + color.2 as u32) / 3;
+    let grey = (mean.saturating_sub(8) / 10).min(23) as u8;
+    let level = 8 + grey * 10;
+
+    match distance(color, Rgb(level, level, level)) < distance(color, cube) {
+        true => 232 + grey,
+        false => 16 + 36 * r as u8 + 6 * g as u8 + b as u8,
+    }
+}
+
+#[test]
+fn ansi_works() {
+    use crate::BoxPuzzle;
+
+    assert_eq!(
+        Colors::from_env(Some("1"), Some("truecolor"), Some("xterm")),
+        Colors::Plain
+    );
+    assert_eq!(
+        Colors::from_env(Some(""), Some("truecolor"), None),
+        Colors::TrueColor
+    );
+    assert_eq!(
+        Colors::from_env(None, None, Some("xterm-256color")),
+        Colors::Ansi256
+    );
+    assert_eq!(
+        Colors::from_env(None, None, Some("xterm-direct")),
+        Colors::TrueColor
+    );
+    assert_eq!(Colors::from_env(None, None, Some("linux")), Colors::Ansi16);
+    assert_eq!(Colors::from_env(None, None, Some("dumb")), Colors::Plain);
+    assert_eq!(Colors::from_env(None, None, None), Colors::Plain);
+
+    assert_eq!(ansi256(Rgb(0xff, 0x00, 0x00)), 196);
+    assert_eq!(ansi256(Rgb(0x80, 0x80, 0x80)), 244);
+    assert_eq!(ansi16(Rgb(0xf0, 0x10, 0x10)), 9);
+
+    // 2 1
+    // 3
+    let board = BoxPuzzle::from_pieces((2, 2), vec![2, 1, 3, 0]).unwrap();
+    let plain = Ansi {
+        colors: Colors::Plain,
+        ..Ansi::default()
+    };
+    assert_eq!(plain.render(&board), board.to_string());
+
+    let grey = Rgb(0x5c, 0x63, 0x70);
+    let styled = Ansi {
+        colors: Colors::TrueColor,
+        scheme: Scheme::Columns,
+        solved: Some(grey),
+        ..plain
+    };
+    assert_eq!(
+        styled.render(&board),
+        "\x1b[38;2;30;30;30;48;2;152;195;121m 2 \x1b[0m\
+         \x1b[38;2;30;30;30;48;2;229;192;123m 1 \x1b[0m\n\
+         \x1b[38;2;30;30;30;48;2;92;99;112m 3 \x1b[0m   \n"
+    );
+    let ansi16 = Ansi {
+        colors: Colors::Ansi16,
+        ..styled
+    };
+    assert!(ansi16.render(&board).starts_with("\x1b[30;100m 2 \x1b[0m"));
+
+    let classes = |scheme: Scheme, (width, height)| {
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| scheme.class((x, y), (width, height)))
+                    .collect()
+            })
+            .collect::<Vec<Vec<_>>>()
+    };
+    assert_eq!(
+        classes(Scheme::Fringe, (4, 4)),
+        [[0, 0, 0, 0], [0, 1, 1, 1], [0, 1, 2, 2], [0, 1, 2, 2]]
+    );
+    assert_eq!(
+        classes(Scheme::RowsAndColumns, (4, 5)),
+        [
+            [0, 0, 0, 0],
+            [1, 2, 2, 2],
+            [1, 3, 4, 4],
+            [1, 3, 5, 5],
+            [1, 3, 5, 5]
+        ]
+    );
+    assert_eq!(
+        classes(Scheme::RowsAndColumns, (5, 3)),
+        [[0, 1, 2, 2, 2], [0, 1, 3, 4, 4], [0, 1, 3, 4, 4]]
+    );
+}
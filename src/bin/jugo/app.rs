@@ -0,0 +1,358 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::time::{Duration, Instant};
+
+use jugo::{BoxPuzzle, Direction, Puzzle, Session, Slide, Solve};
+
+use crate::backend::Backend;
+use crate::keymap::Keymap;
+use crate::ui;
+
+// how often the timer is redrawn while waiting for input
+const TICK: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Slide(Direction),
+    SlideFrom((usize, usize)),
+    Undo,
+    Reset,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    // scrambled, the timer starts with the first move
+    Ready,
+    Solving { since: Instant },
+    Solved { time: Duration },
+}
+
+#[derive(Debug)]
+pub struct App {
+    scramble: BoxPuzzle<u16>,
+    puzzle: BoxPuzzle<u16>,
+    // where the empty piece was before each move, so that sliding from there undoes it
+    history: Vec<(usize, usize)>,
+    state: State,
+    session: Session,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(shape: (usize, usize), session: Session) -> Self {
+        Self::with_puzzle(BoxPuzzle::random(shape), session)
+    }
+
+    pub fn with_puzzle(puzzle: BoxPuzzle<u16>, session: Session) -> Self {
+        Self {
+            scramble: puzzle.clone(),
+            puzzle,
+            history: Vec::new(),
+            state: State::Ready,
+            session,
+            quit: false,
+        }
+    }
+
+    pub fn puzzle(&self) -> &BoxPuzzle<u16> {
+        &self.puzzle
+    }
+
+    pub fn moves(&self) -> usize {
+        self.solution().iter().map(|slide| slide.count).sum()
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        match self.state {
+            State::Ready => Duration::ZERO,
+            State::Solving { since } => now.saturating_duration_since(since),
+            State::Solved { time } => time,
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        matches!(self.state, State::Solved { .. })
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn into_session(self) -> Session {
+        self.session
+    }
+
+    pub fn apply(&mut self, action: Action, now: Instant) {
+        match action {
+            Action::Slide(direction) => {
+                self.slide(now, |puzzle| puzzle.slide_towards(direction, 1));
+            }
+            Action::SlideFrom(from) => {
+                self.slide(now, |puzzle| puzzle.slide_from(from));
+            }
+            Action::Undo => {
+                if self.is_solved() {
+                    return;
+                }
+                if let Some(empty) = self.history.pop() {
+                    self.puzzle.slide_from(empty);
+                }
+            }
+            Action::Reset => {
+                // giving up on a started solve
+                if let State::Solving { since } = self.state {
+                    let time = now.saturating_duration_since(since);
+                    self.session.push(Solve {
+                        dnf: true,
+                        ..Solve::new(&self.scramble, self.solution(), time)
+                    });
+                }
+
+                let session = core::mem::take(&mut self.session);
+                *self = Self::new(self.puzzle.shape(), session);
+            }
+            Action::Quit => self.quit = true,
+        }
+    }
+
+    fn slide(&mut self, now: Instant, slide: impl FnOnce(&mut BoxPuzzle<u16>) -> Option<usize>) {
+        if self.is_solved() {
+            return;
+        }
+
+        let empty = self.empty();
+        if !matches!(slide(&mut self.puzzle), Some(distance) if distance > 0) {
+            return;
+        }
+        self.history.push(empty);
+
+        let since = match self.state {
+            State::Solving { since } => since,
+            _ => now,
+        };
+        self.state = State::Solving { since };
+
+        if self.puzzle.is_solved() {
+            let time = now.saturating_duration_since(since);
+            self.state = State::Solved { time };
+            self.session
+                .push(Solve::new(&self.scramble, self.solution(), time));
+        }
+    }
+
+    fn empty(&self) -> (usize, usize) {
+        self.puzzle
+            .index_of(0)
+            .expect("potential BUG: could not find an empty piece")
+    }
+
+    // every slide so far
+    fn solution(&self) -> Vec<Slide> {
+        let blanks = self.history.iter().copied().chain([self.empty()]);
+        let mut solution = Vec::new();
+        for (before, after) in blanks.clone().zip(blanks.skip(1)) {
+            // the tiles went from where the empty piece is now to where it was
+            let step = |before: usize, after: usize| (before as isize - after as isize).signum();
+            let direction = Direction::try_from((step(before.0, after.0), step(before.1, after.1)))
+                .expect("potential BUG: the empty piece did not move along a line");
+            let count = before.0.abs_diff(after.0) + before.1.abs_diff(after.1);
+            solution.push(Slide::new(direction, count));
+        }
+
+        solution
+    }
+
+    pub fn summary(&self) -> Summary<'_> {
+        Summary {
+            session: &self.session,
+            shape: self.puzzle.shape(),
+        }
+    }
+}
+
+/// What is printed when leaving the TUI.
+pub struct Summary<'a> {
+    session: &'a Session,
+    shape: (usize, usize),
+}
+
+impl Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (width, height) = self.shape;
+        let stats = self.session.stats(self.shape);
+        let (Some(best), Some(worst)) = (stats.best(), stats.worst()) else {
+            return write!(f, "no {width}x{height} solves this session");
+        };
+
+        let solves = stats.solves();
+        let dnfs = solves.iter().filter(|solve| solve.dnf).count();
+        write!(
+            f,
+            "{} {width}x{height} solve{}",
+            solves.len(),
+            if solves.len() == 1 { "" } else { "s" }
+        )?;
+        if dnfs > 0 {
+            write!(f, " ({dnfs} DNF)")?;
+        }
+        writeln!(f, " this session")?;
+
+        writeln!(f, "best: {best}, worst: {worst}")?;
+        if let Some(mean) = stats.mean() {
+            // solves that took no time have no tps to average
+            let tps = solves
+                .iter()
+                .filter(|solve| !solve.dnf)
+                .filter_map(|solve| solve.tps())
+                .collect::<Vec<_>>();
+            write!(f, "mean: {}", ui::format_time(mean))?;
+            if !tps.is_empty() {
+                write!(
+                    f,
+                    " ({:.2} tps)",
+                    tps.iter().sum::<f64>() / tps.len() as f64
+                )?;
+            }
+            writeln!(f)?;
+        }
+        for n in [5, 12, 100] {
+            if let (Some(current), Some(best)) = (stats.average_of(n), stats.best_average_of(n)) {
+                writeln!(f, "ao{n}: {current}, best: {best}")?;
+            }
+        }
+
+        let bests = self
+            .session
+            .personal_bests()
+            .map(|((width, height), time)| format!("{width}x{height} {time}"))
+            .collect::<Vec<_>>();
+        write!(f, "personal bests: {}", bests.join(", "))
+    }
+}
+
+pub fn run<B: Backend>(app: &mut App, keymap: &Keymap, backend: &mut B) -> io::Result<()> {
+    loop {
+        let frame = ui::render(app, keymap, Instant::now());
+        backend.draw(&frame)?;
+
+        if app.should_quit() {
+            return Ok(());
+        }
+
+        if let Some(action) = backend
+            .poll(TICK)?
+            .and_then(|input| ui::action(&frame, keymap, input))
+        {
+            app.apply(action, Instant::now());
+        }
+    }
+}
+
+#[test]
+fn solving_records_time_and_moves() {
+    use Direction::*;
+
+    let start = Instant::now();
+    let mut app = App::with_puzzle(BoxPuzzle::default().cast(), Session::new());
+
+    // solved already, but nothing has been done yet
+    assert!(!app.is_solved());
+
+    app.apply(Action::Slide(Down), start);
+    app.apply(Action::Slide(Right), start + Duration::from_secs(1));
+    app.apply(Action::Undo, start + Duration::from_secs(2));
+    assert_eq!(app.moves(), 1);
+    assert_eq!(
+        app.elapsed(start + Duration::from_secs(3)),
+        Duration::from_secs(3)
+    );
+
+    // sliding into a wall is not a move
+    app.apply(Action::Slide(Left), start + Duration::from_secs(3));
+    assert_eq!(app.moves(), 1);
+
+    app.apply(Action::SlideFrom((3, 3)), start + Duration::from_secs(4));
+    assert!(app.is_solved());
+    assert_eq!(
+        app.session().solves(),
+        [Solve::new(
+            &BoxPuzzle::<u8>::default(),
+            vec![Slide::new(Down, 1), Slide::new(Up, 1)],
+            Duration::from_secs(4)
+        )]
+    );
+
+    // the board is frozen until the next scramble
+    app.apply(Action::Slide(Down), start + Duration::from_secs(5));
+    app.apply(Action::Undo, start + Duration::from_secs(5));
+    assert_eq!(app.moves(), 2);
+    assert_eq!(
+        app.elapsed(start + Duration::from_secs(9)),
+        Duration::from_secs(4)
+    );
+
+    app.apply(Action::Reset, start + Duration::from_secs(10));
+    assert_eq!(app.moves(), 0);
+    assert_eq!(app.session().solves().len(), 1);
+    assert_eq!(app.puzzle().shape(), (4, 4));
+
+    // rescrambling in the middle of a solve is a DNF
+    let empty = app.empty();
+    let from = (empty.0, (empty.1 + 2) % 4);
+    app.apply(Action::SlideFrom(from), start + Duration::from_secs(11));
+    app.apply(Action::Reset, start + Duration::from_secs(13));
+    let dnf = &app.session().solves()[1];
+    assert!(dnf.dnf);
+    assert_eq!(dnf.time, Duration::from_secs(2));
+    assert_eq!(dnf.stm(), from.1.abs_diff(empty.1));
+    assert_eq!(dnf.mtm(), 1);
+
+    // solved by the first move, which takes no time at all
+    let mut scramble = BoxPuzzle::<u8>::default().cast();
+    scramble.slide_from((3, 2));
+    let mut app = App::with_puzzle(scramble, Session::new());
+    app.apply(Action::Slide(Up), start);
+    assert_eq!(app.session().solves()[0].tps(), None);
+    let summary = app.summary().to_string();
+    assert!(summary.contains("\nmean: 0.000\n"), "{summary}");
+}
+
+#[test]
+fn headless_session() {
+    use crate::backend::{Headless, Input, Key};
+
+    let mut app = App::with_puzzle(BoxPuzzle::default().cast(), Session::new());
+    let keymap = Keymap::new((4, 4));
+    let mut backend = Headless::new([
+        Input::Key(Key::Down),
+        Input::Key(Key::Down),
+        Input::Key(Key::Backspace),
+    ]);
+
+    // clicking the cell right below the empty piece, wherever the board was drawn
+    let layout = ui::render(&app, &keymap, Instant::now()).board;
+    let (column, row) = layout.cell_origin((3, 3));
+    backend.push(Input::Click { column, row });
+
+    run(&mut app, &keymap, &mut backend).unwrap();
+
+    assert!(app.should_quit());
+    assert_eq!(app.session().solves().len(), 1);
+    assert_eq!(app.session().solves()[0].mtm(), 2);
+    assert!(backend
+        .frames()
+        .last()
+        .unwrap()
+        .lines
+        .iter()
+        .any(|line| line.contains("solved")));
+
+    let summary = app.summary().to_string();
+    assert!(summary.starts_with("1 4x4 solve this session"), "{summary}");
+    assert!(summary.contains("\npersonal bests: 4x4 "), "{summary}");
+}
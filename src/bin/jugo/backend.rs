@@ -0,0 +1,160 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::{cursor, queue, style, terminal};
+
+use serde::Deserialize;
+
+use crate::ui::Frame;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Backspace,
+    Esc,
+    Char(char),
+    Ctrl(char),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Key(Key),
+    Click {
+        column: u16,
+        row: u16,
+    },
+    // no more input will ever come; only the headless backend runs out of it
+    #[cfg_attr(not(test), allow(dead_code))]
+    Closed,
+}
+
+pub trait Backend {
+    fn draw(&mut self, frame: &Frame) -> io::Result<()>;
+
+    /// Waits for at most `timeout`, returning `None` if nothing happened.
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<Input>>;
+}
+
+/// The real terminal, in raw mode on the alternate screen until dropped.
+pub struct Terminal<W: Write> {
+    out: W,
+}
+
+impl<W: Write> Terminal<W> {
+    pub fn new(out: W) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        // from here on, dropping the terminal on an error restores raw mode
+        let mut terminal = Self { out };
+        crossterm::execute!(
+            terminal.out,
+            terminal::EnterAlternateScreen,
+            EnableMouseCapture,
+            cursor::Hide
+        )?;
+
+        Ok(terminal)
+    }
+}
+
+impl<W: Write> Drop for Terminal<W> {
+    fn drop(&mut self) {
+        // best effort; there is nowhere left to report errors to
+        let _ = crossterm::execute!(
+            self.out,
+            cursor::Show,
+            DisableMouseCapture,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl<W: Write> Backend for Terminal<W> {
+    fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        queue!(self.out, terminal::Clear(terminal::ClearType::All))?;
+        for (row, line) in frame.lines.iter().enumerate() {
+            queue!(self.out, cursor::MoveTo(0, row as u16), style::Print(line))?;
+        }
+        self.out.flush()
+    }
+
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<Input>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+
+        Ok(match event::read()? {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) => {
+                let key = match code {
+                    KeyCode::Up => Key::Up,
+                    KeyCode::Down => Key::Down,
+                    KeyCode::Left => Key::Left,
+                    KeyCode::Right => Key::Right,
+                    KeyCode::Backspace => Key::Backspace,
+                    KeyCode::Esc => Key::Esc,
+                    KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => Key::Ctrl(c),
+                    KeyCode::Char(c) => Key::Char(c),
+                    _ => return Ok(None),
+                };
+                Some(Input::Key(key))
+            }
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                ..
+            }) => Some(Input::Click { column, row }),
+            _ => None,
+        })
+    }
+}
+
+/// Plays back scripted input and keeps every frame drawn, for tests.
+#[cfg(test)]
+pub struct Headless {
+    inputs: std::collections::VecDeque<Input>,
+    frames: Vec<Frame>,
+}
+
+#[cfg(test)]
+impl Headless {
+    pub fn new(inputs: impl IntoIterator<Item = Input>) -> Self {
+        Self {
+            inputs: inputs.into_iter().collect(),
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, input: Input) {
+        self.inputs.push_back(input);
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+}
+
+#[cfg(test)]
+impl Backend for Headless {
+    fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        self.frames.push(frame.clone());
+        Ok(())
+    }
+
+    fn poll(&mut self, _: Duration) -> io::Result<Option<Input>> {
+        Ok(Some(self.inputs.pop_front().unwrap_or(Input::Closed)))
+    }
+}
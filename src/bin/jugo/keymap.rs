@@ -0,0 +1,370 @@
+//! Key bindings, generated for any board size and optionally overridden from a TOML or JSON
+//! file. Every section is optional:
+//!
+//! ```toml
+//! # whether the arrows move the tile (the default) or the empty piece
+//! arrows = "blank"
+//!
+//! [directions]
+//! up = ["Up", "k"]
+//!
+//! [actions]
+//! undo = ["Backspace", "C-z"]
+//! reset = ["Space"]
+//! quit = ["q", "Esc", "C-c"]
+//!
+//! # one string per row and one key per cell, for each board size
+//! [cells]
+//! "4x4" = ["4567", "rtyu", "fghj", "vbnm"]
+//! ```
+//!
+//! When a key is bound twice, directions and actions win over cells.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::str::FromStr;
+use std::{fs, io};
+
+use jugo::{Convention, Direction};
+use serde::Deserialize;
+
+use crate::app::Action;
+use crate::backend::Key;
+
+// what per-cell keys are picked from when there is nothing in the file, centred on the board
+const KEYBOARD: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl;", "zxcvbnm,./"];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Arrows {
+    #[default]
+    Tile,
+    Blank,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Directions {
+    up: Option<Vec<Key>>,
+    down: Option<Vec<Key>>,
+    left: Option<Vec<Key>>,
+    right: Option<Vec<Key>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Actions {
+    undo: Option<Vec<Key>>,
+    reset: Option<Vec<Key>>,
+    quit: Option<Vec<Key>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    arrows: Arrows,
+    directions: Directions,
+    actions: Actions,
+    cells: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    Cells { shape: String },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{error}"),
+            Error::Toml(error) => write!(f, "{error}"),
+            Error::Json(error) => write!(f, "{error}"),
+            Error::Cells { shape } => write!(f, "cells for {shape} do not fit a {shape} board"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    // in order of precedence; the first binding of a key is the one that counts
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Keymap {
+    /// The default bindings for a board of the given shape.
+    pub fn new(shape: (usize, usize)) -> Self {
+        Self::from_config(Config::default(), shape).expect("potential BUG: invalid default keymap")
+    }
+
+    /// Loads a keymap, as JSON if the file name ends with `.json` and as TOML otherwise.
+    pub fn load(path: impl AsRef<Path>, shape: (usize, usize)) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(Error::Io)?;
+
+        match path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            true => Self::from_json(&source, shape),
+            false => Self::from_toml(&source, shape),
+        }
+    }
+
+    pub fn from_toml(source: &str, shape: (usize, usize)) -> Result<Self, Error> {
+        Self::from_config(toml::from_str(source).map_err(Error::Toml)?, shape)
+    }
+
+    pub fn from_json(source: &str, shape: (usize, usize)) -> Result<Self, Error> {
+        Self::from_config(serde_json::from_str(source).map_err(Error::Json)?, shape)
+    }
+
+    fn from_config(config: Config, shape: (usize, usize)) -> Result<Self, Error> {
+        use Direction::*;
+
+        let mut keymap = Self {
+            bindings: Vec::new(),
+        };
+
+        let Directions {
+            up,
+            down,
+            left,
+            right,
+        } = config.directions;
+        for (keys, default, direction) in [
+            (up, Key::Up, Up),
+            (down, Key::Down, Down),
+            (left, Key::Left, Left),
+            (right, Key::Right, Right),
+        ] {
+            let direction = match config.arrows {
+                Arrows::Tile => Convention::Tile,
+                Arrows::Blank => Convention::Blank,
+            }
+            .tile(direction);
+            keymap.bind(
+                keys.unwrap_or_else(|| vec![default]),
+                Action::Slide(direction),
+            );
+        }
+
+        let Actions { undo, reset, quit } = config.actions;
+        let undo = undo.unwrap_or_else(|| vec![Key::Backspace, Key::Ctrl('z')]);
+        let reset = reset.unwrap_or_else(|| vec![Key::Char(' ')]);
+        let quit = quit.unwrap_or_else(|| vec![Key::Char('q'), Key::Esc, Key::Ctrl('c')]);
+        keymap.bind(undo, Action::Undo);
+        keymap.bind(reset, Action::Reset);
+        keymap.bind(quit, Action::Quit);
+
+        let (width, height) = shape;
+        let name = format!("{width}x{height}");
+        let rows = match config.cells.get(&name) {
+            Some(rows) => {
+                let fits =
+                    rows.len() == height && rows.iter().all(|row| row.chars().count() == width);
+                if !fits {
+                    return Err(Error::Cells { shape: name });
+                }
+                rows.iter().map(|row| row.chars().collect()).collect()
+            }
+            None => generate_cells(shape),
+        };
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, key) in row.into_iter().enumerate() {
+                keymap.bind([Key::Char(key)], Action::SlideFrom((x, y)));
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    fn bind(&mut self, keys: impl IntoIterator<Item = Key>, action: Action) {
+        for key in keys {
+            if self.action(key).is_none() {
+                self.bindings.push((key, action));
+            }
+        }
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find_map(|&(bound, action)| (bound == key).then_some(action))
+    }
+
+    /// The first key bound to an action, to tell the player about.
+    pub fn key_for(&self, action: Action) -> Option<Key> {
+        self.bindings
+            .iter()
+            .find_map(|&(key, bound)| (bound == action).then_some(key))
+    }
+}
+
+// the keys under a window of the board's size in the middle of `KEYBOARD`; boards larger than
+// that get keys for as many cells as there are keys
+fn generate_cells((width, height): (usize, usize)) -> Vec<Vec<char>> {
+    let columns = KEYBOARD[0].len();
+    let left = columns.saturating_sub(width) / 2;
+    let top = KEYBOARD.len().saturating_sub(height) / 2;
+
+    KEYBOARD
+        .iter()
+        .skip(top)
+        .take(height)
+        .map(|row| row.chars().skip(left).take(width).collect())
+        .collect()
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Key::Char(c));
+        }
+
+        if let Some(c) = name.strip_prefix("C-") {
+            let mut chars = c.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                return Ok(Key::Ctrl(c));
+            }
+        }
+
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "backspace" => Key::Backspace,
+            "esc" => Key::Esc,
+            "space" => Key::Char(' '),
+            _ => return Err(format!("unknown key: {name}")),
+        })
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Up => write!(f, "Up"),
+            Key::Down => write!(f, "Down"),
+            Key::Left => write!(f, "Left"),
+            Key::Right => write!(f, "Right"),
+            Key::Backspace => write!(f, "Backspace"),
+            Key::Esc => write!(f, "Esc"),
+            Key::Char(' ') => write!(f, "Space"),
+            Key::Char(c) => write!(f, "{c}"),
+            Key::Ctrl(c) => write!(f, "C-{c}"),
+        }
+    }
+}
+
+#[test]
+fn default_keymaps_work() {
+    use Direction::*;
+
+    let keymap = Keymap::new((4, 4));
+    for (y, row) in ["4567", "rtyu", "fghj", "vbnm"].into_iter().enumerate() {
+        for (x, key) in row.chars().enumerate() {
+            assert_eq!(
+                keymap.action(Key::Char(key)),
+                Some(Action::SlideFrom((x, y)))
+            );
+        }
+    }
+    assert_eq!(keymap.action(Key::Up), Some(Action::Slide(Up)));
+    assert_eq!(keymap.action(Key::Ctrl('z')), Some(Action::Undo));
+    assert_eq!(keymap.action(Key::Char('3')), None);
+
+    // quitting wins over the cell under `q`
+    let keymap = Keymap::new((10, 4));
+    assert_eq!(
+        keymap.action(Key::Char('w')),
+        Some(Action::SlideFrom((1, 1)))
+    );
+    assert_eq!(keymap.action(Key::Char('q')), Some(Action::Quit));
+
+    // boards larger than the keyboard only get keys for part of their cells
+    let keymap = Keymap::new((12, 6));
+    assert_eq!(
+        keymap.action(Key::Char('1')),
+        Some(Action::SlideFrom((0, 0)))
+    );
+    assert_eq!(
+        keymap.action(Key::Char('/')),
+        Some(Action::SlideFrom((9, 3)))
+    );
+
+    for name in ["a", "C-z", "Space", "Backspace", "Up"] {
+        assert_eq!(name.parse::<Key>().unwrap().to_string(), name);
+    }
+    assert!("C-".parse::<Key>().is_err());
+    assert!("Enter".parse::<Key>().is_err());
+}
+
+#[test]
+fn loading_keymaps_works() {
+    use Direction::*;
+
+    let toml = r#"
+        arrows = "blank"
+
+        [directions]
+        up = ["k"]
+
+        [actions]
+        undo = ["u"]
+
+        [cells]
+        "2x2" = ["ab", "cd"]
+    "#;
+    let keymap = Keymap::from_toml(toml, (2, 2)).unwrap();
+    assert_eq!(keymap.action(Key::Char('k')), Some(Action::Slide(Down)));
+    assert_eq!(keymap.action(Key::Up), None);
+    assert_eq!(keymap.action(Key::Left), Some(Action::Slide(Right)));
+    assert_eq!(keymap.action(Key::Char('u')), Some(Action::Undo));
+    assert_eq!(keymap.action(Key::Backspace), None);
+    assert_eq!(keymap.action(Key::Esc), Some(Action::Quit));
+    assert_eq!(
+        keymap.action(Key::Char('d')),
+        Some(Action::SlideFrom((1, 1)))
+    );
+    assert_eq!(keymap.key_for(Action::Undo), Some(Key::Char('u')));
+
+    // cells for other sizes are left alone
+    let keymap = Keymap::from_toml(toml, (3, 3)).unwrap();
+    assert_eq!(keymap.action(Key::Char('a')), None);
+    assert_eq!(
+        keymap.action(Key::Char('4')),
+        Some(Action::SlideFrom((0, 0)))
+    );
+
+    let json = r#"{ "actions": { "reset": ["Enter"] } }"#;
+    assert!(matches!(
+        Keymap::from_json(json, (4, 4)),
+        Err(Error::Json(_))
+    ));
+    let json = r#"{ "cells": { "2x2": ["ab", "c"] } }"#;
+    assert!(matches!(
+        Keymap::from_json(json, (2, 2)),
+        Err(Error::Cells { .. })
+    ));
+    let json = r#"{ "arrows": "tile", "actions": { "reset": ["C-r"] } }"#;
+    let keymap = Keymap::from_json(json, (4, 4)).unwrap();
+    assert_eq!(keymap.action(Key::Ctrl('r')), Some(Action::Reset));
+}
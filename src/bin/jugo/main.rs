@@ -0,0 +1,113 @@
+mod app;
+mod backend;
+mod keymap;
+mod ui;
+
+use std::io;
+use std::process::ExitCode;
+
+use app::App;
+use backend::Terminal;
+use jugo::Session;
+use keymap::Keymap;
+
+const USAGE: &str = "usage: jugo [--keymap FILE] [--session FILE] [WIDTHxHEIGHT]";
+
+fn parse_shape(arg: &str) -> Option<(usize, usize)> {
+    let (width, height) = arg.split_once(['x', 'X'])?;
+    let shape = (width.parse().ok()?, height.parse().ok()?);
+
+    matches!(shape, (2.., 2..)).then_some(shape)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut keymap_path = None;
+    let mut session_path = None;
+    let mut shape = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "--keymap" if keymap_path.is_none() => match args.next() {
+                Some(path) => keymap_path = Some(path),
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "--session" if session_path.is_none() => match args.next() {
+                Some(path) => session_path = Some(path),
+                None => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            _ if shape.is_none() => match parse_shape(&arg) {
+                Some(parsed) => shape = Some(parsed),
+                None => {
+                    eprintln!("invalid board size: {arg}\n{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let shape = shape.unwrap_or((4, 4));
+    let keymap = match keymap_path {
+        None => Keymap::new(shape),
+        Some(path) => match Keymap::load(&path, shape) {
+            Ok(keymap) => keymap,
+            Err(error) => {
+                eprintln!("jugo: {path}: {error}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    // solves are only kept for later when there is somewhere to keep them
+    let session = match &session_path {
+        None => Session::new(),
+        Some(path) => match Session::load(path) {
+            Ok(session) => session,
+            Err(error) => {
+                eprintln!("jugo: {path}: {error}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let mut app = App::new(shape, session);
+    let result = Terminal::new(io::stdout())
+        .and_then(|mut terminal| app::run(&mut app, &keymap, &mut terminal));
+
+    if let Err(error) = result {
+        eprintln!("jugo: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    println!("{}", app.summary());
+    if let Some(path) = session_path {
+        if let Err(error) = app.into_session().save(&path) {
+            eprintln!("jugo: {path}: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+#[test]
+fn parse_shape_works() {
+    assert_eq!(parse_shape("4x4"), Some((4, 4)));
+    assert_eq!(parse_shape("3X5"), Some((3, 5)));
+    assert_eq!(parse_shape("1x5"), None);
+    assert_eq!(parse_shape("4"), None);
+    assert_eq!(parse_shape("ax4"), None);
+}
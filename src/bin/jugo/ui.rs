@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+use jugo::{Direction, Puzzle, Time};
+
+use crate::app::{Action, App};
+use crate::backend::Input;
+use crate::keymap::Keymap;
+
+const MARGIN: u16 = 2;
+
+/// Everything on screen, as plain lines, plus where the board was drawn.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub lines: Vec<String>,
+    pub board: Layout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    origin: (u16, u16),
+    cell_width: u16,
+    shape: (usize, usize),
+}
+
+impl Layout {
+    /// The cell under a terminal position, if any.
+    pub fn cell_at(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        let x = column.checked_sub(self.origin.0)? / self.cell_width;
+        let y = row.checked_sub(self.origin.1)?;
+        let (x, y) = (x as usize, y as usize);
+
+        (x < self.shape.0 && y < self.shape.1).then_some((x, y))
+    }
+
+    /// The terminal position of the top-left corner of a cell.
+    #[cfg(test)]
+    pub fn cell_origin(&self, (x, y): (usize, usize)) -> (u16, u16) {
+        (
+            self.origin.0 + x as u16 * self.cell_width,
+            self.origin.1 + y as u16,
+        )
+    }
+}
+
+pub fn render(app: &App, keymap: &Keymap, now: Instant) -> Frame {
+    let puzzle = app.puzzle();
+    let (width, height) = puzzle.shape();
+    let digits = ((width * height - 1) as f32).log10() as usize + 1;
+
+    let mut lines = vec![format!("jugo {width}x{height}"), String::new()];
+
+    let board = Layout {
+        origin: (MARGIN, lines.len() as u16),
+        cell_width: digits as u16 + 1,
+        shape: (width, height),
+    };
+    for y in 0..height {
+        let mut line = " ".repeat(MARGIN as usize);
+        for piece in puzzle.row(y) {
+            match piece {
+                0 => line += &format!("{: >digits$} ", ""),
+                piece => line += &format!("{piece: >digits$} "),
+            }
+        }
+        lines.push(line);
+    }
+
+    lines.push(String::new());
+    let stats = app.session().stats((width, height));
+    let mut status = format!(
+        "time {}  moves {}  solves {}",
+        format_time(app.elapsed(now)),
+        app.moves(),
+        stats.solves().len()
+    );
+    if let Some(ao5) = stats.average_of(5) {
+        status += &format!("  ao5 {ao5}");
+    }
+    lines.push(status);
+    lines.push(help(app, keymap));
+
+    Frame { lines, board }
+}
+
+// what the keys are, skipping actions without any
+fn help(app: &App, keymap: &Keymap) -> String {
+    let hint = |action, what| keymap.key_for(action).map(|key| format!("{key} {what}"));
+
+    let hints = match app.is_solved() {
+        true => vec![
+            Some("solved!".to_owned()),
+            hint(Action::Reset, "for a new scramble"),
+        ],
+        false => vec![
+            Some("click to slide".to_owned()),
+            slide_hint(keymap),
+            hint(Action::Undo, "to undo"),
+            hint(Action::Reset, "to rescramble"),
+            hint(Action::Quit, "to quit"),
+        ],
+    };
+
+    hints.into_iter().flatten().collect::<Vec<_>>().join(", ")
+}
+
+fn slide_hint(keymap: &Keymap) -> Option<String> {
+    let keys = Direction::ALL
+        .into_iter()
+        .map(|direction| {
+            keymap
+                .key_for(Action::Slide(direction))
+                .map(|key| key.to_string())
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(format!("{} to slide", keys.join("/")))
+}
+
+pub fn action(frame: &Frame, keymap: &Keymap, input: Input) -> Option<Action> {
+    match input {
+        Input::Key(key) => keymap.action(key),
+        Input::Click { column, row } => frame.board.cell_at(column, row).map(Action::SlideFrom),
+        Input::Closed => Some(Action::Quit),
+    }
+}
+
+pub fn format_time(time: Duration) -> String {
+    Time::Finished(time).to_string()
+}
+
+#[test]
+fn clicks_map_to_cells() {
+    let layout = Layout {
+        origin: (2, 2),
+        cell_width: 3,
+        shape: (4, 4),
+    };
+
+    assert_eq!(layout.cell_at(2, 2), Some((0, 0)));
+    assert_eq!(layout.cell_at(4, 2), Some((0, 0)));
+    assert_eq!(layout.cell_at(5, 3), Some((1, 1)));
+    assert_eq!(layout.cell_at(13, 5), Some((3, 3)));
+    assert_eq!(layout.cell_at(14, 5), None);
+    assert_eq!(layout.cell_at(1, 2), None);
+    assert_eq!(layout.cell_at(2, 6), None);
+    assert_eq!(
+        layout.cell_at(layout.cell_origin((2, 1)).0, 3),
+        Some((2, 1))
+    );
+
+    assert_eq!(format_time(Duration::from_millis(9_876)), "9.876");
+    assert_eq!(format_time(Duration::from_millis(61_005)), "1:01.005");
+}
@@ -0,0 +1,105 @@
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// How tiles are grouped into colours, by where they belong on the solved board.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scheme {
+    /// Every tile the same.
+    #[default]
+    Plain,
+    Rows,
+    Columns,
+    Chessboard,
+    /// L-shaped fringes from the top left, down to the last 2x2.
+    Fringe,
+    /// Rows and columns in the order a reduction solves them: the longer side first, then
+    /// alternating, down to the last 2x2.
+    RowsAndColumns,
+}
+
+impl Scheme {
+    /// The colour class of a tile whose solved position is `goal` on a board of `shape`.
+    pub fn class(&self, (x, y): (usize, usize), (width, height): (usize, usize)) -> usize {
+        match self {
+            Scheme::Plain => 0,
+            Scheme::Rows => y,
+            Scheme::Columns => x,
+            Scheme::Chessboard => (x + y) % 2,
+            Scheme::Fringe => x.min(y).min(width.min(height).saturating_sub(2)),
+            Scheme::RowsAndColumns => {
+                let (mut rows, mut columns) = (0, 0);
+                for class in 0.. {
+                    let (left, top) = (width - columns, height - rows);
+                    if left <= 2 && top <= 2 {
+                        return class;
+                    }
+                    if top > left || (top == left && rows <= columns) {
+                        if y == rows {
+                            return class;
+                        }
+                        rows += 1;
+                    } else {
+                        if x == columns {
+                            return class;
+                        }
+                        columns += 1;
+                    }
+                }
+                unreachable!()
+            }
+        }
+    }
+}
+
+/// Colours to draw with. Tiles cycle through `tiles` by colour class.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Palette {
+    pub background: Rgb,
+    pub text: Rgb,
+    tiles: Vec<Rgb>,
+}
+
+impl Palette {
+    /// Returns `None` if `tiles` is empty, since every tile needs a colour.
+    pub fn new(background: Rgb, text: Rgb, tiles: Vec<Rgb>) -> Option<Self> {
+        (!tiles.is_empty()).then_some(Self {
+            background,
+            text,
+            tiles,
+        })
+    }
+
+    /// The colours tiles cycle through, never empty.
+    pub fn tiles(&self) -> &[Rgb] {
+        &self.tiles
+    }
+
+    pub fn tile(&self, class: usize) -> Rgb {
+        self.tiles[class % self.tiles.len()]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: Rgb(0x28, 0x2c, 0x34),
+            text: Rgb(0x1e, 0x1e, 0x1e),
+            tiles: vec![
+                Rgb(0xe5, 0xc0, 0x7b),
+                Rgb(0x98, 0xc3, 0x79),
+                Rgb(0x61, 0xaf, 0xef),
+                Rgb(0xc6, 0x78, 0xdd),
+                Rgb(0xe0, 0x6c, 0x75),
+                Rgb(0x56, 0xb6, 0xc2),
+            ],
+        }
+    }
+}
@@ -1,4 +1,12 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use core::fmt::{self, Display};
+use core::str::FromStr;
+
+use crate::DirectionError;
+
+/// Which way pieces go. [`Puzzle::slide_towards`](crate::Puzzle::slide_towards) takes the way
+/// the tiles go, see [`Convention`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Up,
     Down,
@@ -6,6 +14,48 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub const fn opposite(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// A quarter turn clockwise.
+    pub const fn rotate_clockwise(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// A quarter turn counterclockwise.
+    pub const fn rotate_counterclockwise(self) -> Self {
+        self.rotate_clockwise().opposite()
+    }
+
+    pub const fn arrow(self) -> char {
+        match self {
+            Direction::Up => '↑',
+            Direction::Down => '↓',
+            Direction::Left => '←',
+            Direction::Right => '→',
+        }
+    }
+}
+
 impl From<Direction> for (isize, isize) {
     fn from(direction: Direction) -> Self {
         match direction {
@@ -16,3 +66,131 @@ impl From<Direction> for (isize, isize) {
         }
     }
 }
+
+impl TryFrom<(isize, isize)> for Direction {
+    type Error = DirectionError;
+
+    /// The direction of a single step, the inverse of `From<Direction> for (isize, isize)`.
+    fn try_from(offset: (isize, isize)) -> Result<Self, Self::Error> {
+        match offset {
+            (0, -1) => Ok(Direction::Up),
+            (0, 1) => Ok(Direction::Down),
+            (-1, 0) => Ok(Direction::Left),
+            (1, 0) => Ok(Direction::Right),
+            offset => Err(DirectionError::NotAStep { offset }),
+        }
+    }
+}
+
+/// `U`, `D`, `L` and `R`.
+impl Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = match self {
+            Direction::Up => "U",
+            Direction::Down => "D",
+            Direction::Left => "L",
+            Direction::Right => "R",
+        };
+        f.write_str(letter)
+    }
+}
+
+/// `U`, `D`, `L` and `R` in either case, or an arrow.
+impl FromStr for Direction {
+    type Err = DirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "U" | "u" | "↑" => Ok(Direction::Up),
+            "D" | "d" | "↓" => Ok(Direction::Down),
+            "L" | "l" | "←" => Ok(Direction::Left),
+            "R" | "r" | "→" => Ok(Direction::Right),
+            _ => Err(DirectionError::InvalidNotation),
+        }
+    }
+}
+
+/// Which piece a [`Direction`] tells the way of: the tiles that slide, or the empty piece,
+/// which goes the other way. See
+/// [`Puzzle::slide_towards_as`](crate::Puzzle::slide_towards_as).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Convention {
+    /// What [`Puzzle::slide_towards`](crate::Puzzle::slide_towards) takes.
+    #[default]
+    Tile,
+    Blank,
+}
+
+impl Convention {
+    /// The way the tiles go, `direction` being in this convention.
+    pub const fn tile(self, direction: Direction) -> Direction {
+        match self {
+            Convention::Tile => direction,
+            Convention::Blank => direction.opposite(),
+        }
+    }
+
+    /// The way the empty piece goes, `direction` being in this convention.
+    pub const fn blank(self, direction: Direction) -> Direction {
+        match self {
+            Convention::Tile => direction.opposite(),
+            Convention::Blank => direction,
+        }
+    }
+}
+
+#[test]
+fn direction_works() {
+    use crate::{Puzzle, StackPuzzle};
+    use Direction::*;
+
+    for direction in Direction::ALL {
+        assert_eq!(direction.opposite().opposite(), direction);
+        assert_eq!(
+            direction.rotate_clockwise().rotate_counterclockwise(),
+            direction
+        );
+        assert_eq!(
+            Direction::try_from(<(isize, isize)>::from(direction)),
+            Ok(direction)
+        );
+        #[cfg(feature = "std")]
+        {
+            assert_eq!(direction.to_string().parse(), Ok(direction));
+            assert_eq!(direction.arrow().to_string().parse(), Ok(direction));
+        }
+        assert_eq!(
+            Convention::Blank.tile(Convention::Tile.blank(direction)),
+            direction
+        );
+    }
+
+    assert_eq!(Up.rotate_clockwise(), Right);
+    assert_eq!(Left.rotate_counterclockwise(), Down);
+    assert_eq!("l".parse(), Ok(Left));
+    assert_eq!(
+        "x".parse::<Direction>(),
+        Err(DirectionError::InvalidNotation)
+    );
+    assert_eq!(
+        Direction::try_from((1, 1)),
+        Err(DirectionError::NotAStep { offset: (1, 1) })
+    );
+    assert_eq!(Convention::Tile.blank(Up), Down);
+    assert_eq!(Convention::Blank.tile(Up), Down);
+
+    // the same slide, told either way, on the same board
+    let board = StackPuzzle::<4, 4, u8>::default();
+    let mut tile = board.clone();
+    let mut blank = board.clone();
+    assert_eq!(tile.slide_towards_as(Down, 2, Convention::Tile), Some(2));
+    assert_eq!(blank.slide_towards_as(Up, 2, Convention::Blank), Some(2));
+    assert_eq!(tile, blank);
+    assert_eq!(tile.index_of(0), Some((3, 1)));
+
+    let mut blank = board.clone();
+    assert_eq!(blank.slide_towards_as(Left, 3, Convention::Blank), Some(3));
+    assert_eq!(blank.index_of(0), Some((0, 3)));
+    assert_eq!(blank.slide_towards_as(Left, 1, Convention::Blank), None);
+    assert_eq!(blank.slide_towards_as(Right, 1, Convention::Tile), None);
+}
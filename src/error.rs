@@ -0,0 +1,102 @@
+use core::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The source has a different `(width, height)` than the destination can hold.
+    ShapeMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// The piece at `position` does not fit in the destination piece type.
+    PieceOutOfRange { position: (usize, usize) },
+    /// The pieces are not `0..width * height` in some order.
+    InvalidPieces,
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ShapeMismatch { expected, found } => write!(
+                f,
+                "shape mismatch: expected {}x{}, found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            Self::PieceOutOfRange { position } => write!(
+                f,
+                "piece at ({}, {}) is out of range for the destination type",
+                position.0, position.1
+            ),
+            Self::InvalidPieces => write!(f, "pieces are not a permutation of 0..width*height"),
+        }
+    }
+}
+
+impl core::error::Error for ConversionError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The starting board is not a valid puzzle.
+    InvalidBoard(ConversionError),
+    /// The move at `index` cannot be made, or does not do what it says.
+    IllegalMove { index: usize },
+    /// The move at `index` happened before the previous one.
+    Unordered { index: usize },
+    /// Every move was made, but the puzzle is not solved.
+    NotSolved,
+    /// The starting board cannot be solved at all.
+    Unsolvable,
+    /// The replay is on a kind of board this cannot handle.
+    Unsupported,
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidBoard(error) => write!(f, "invalid starting board: {error}"),
+            Self::IllegalMove { index } => write!(f, "move {index} is illegal"),
+            Self::Unordered { index } => write!(f, "move {index} happened before the previous one"),
+            Self::NotSolved => write!(f, "the puzzle is not solved after the last move"),
+            Self::Unsolvable => write!(f, "the starting board cannot be solved"),
+            Self::Unsupported => write!(f, "this kind of board is not supported"),
+        }
+    }
+}
+
+impl core::error::Error for ReplayError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotationError {
+    /// The move at `index`, counting from 0, could not be parsed.
+    InvalidMove { index: usize },
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMove { index } => write!(f, "move {index} is not valid notation"),
+        }
+    }
+}
+
+impl core::error::Error for NotationError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectionError {
+    /// The offset is not a single step up, down, left or right.
+    NotAStep { offset: (isize, isize) },
+    /// Not one of `U`, `D`, `L`, `R` or an arrow.
+    InvalidNotation,
+}
+
+impl Display for DirectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAStep { offset } => {
+                write!(f, "({}, {}) is not a single step", offset.0, offset.1)
+            }
+            Self::InvalidNotation => write!(f, "not a direction"),
+        }
+    }
+}
+
+impl core::error::Error for DirectionError {}
@@ -1,14 +1,17 @@
-use num::Integer;
-use omniswap::{rotate, swap};
-use rand::{seq::SliceRandom, Rng};
+use alloc::boxed::Box;
+use alloc::vec;
+use rand::Rng;
 
+use core::cmp::Ordering;
 use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
 use core::hint::unreachable_unchecked;
 use core::mem;
 use core::ops::Index;
-use core::{cmp::Ordering, iter::once};
 
-use crate::{is_solvable, Piece, Puzzle};
+#[cfg(feature = "ndarray")]
+use crate::NdArrayPuzzle;
+use crate::{random_pieces, ConversionError, Piece, Position, Puzzle, StackPuzzle};
 
 #[derive(Clone)]
 pub struct BoxPuzzle<T: Piece> {
@@ -59,28 +62,85 @@ impl<T: Piece + Debug> Debug for BoxPuzzle<T> {
 impl<T: Piece> Index<(usize, usize)> for BoxPuzzle<T> {
     type Output = T;
     fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
-        &self.inner[x * self.width + y]
+        &self.inner[y * self.width + x]
     }
 }
 
-// #[derive(Debug)]
-// struct Iter<'a, T: 'a> {
-//     inner: core::slice::Iter<'a, T>,
-// }
-// impl<'a, T> Iterator for Iter<'a, T> {
-//     type Item = &'a T;
-//     fn next(&mut self) -> Option<Self::Item> {
-//         self.inner.next()
-//     }
-// }
+impl<T: Piece> Index<Position> for BoxPuzzle<T> {
+    type Output = T;
+    fn index(&self, Position { x, y }: Position) -> &Self::Output {
+        &self[(x, y)]
+    }
+}
+
+impl<T: Piece> PartialEq for BoxPuzzle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.inner == other.inner
+    }
+}
+
+impl<T: Piece> Eq for BoxPuzzle<T> {}
+
+impl<const W: usize, const H: usize, T: Piece> PartialEq<StackPuzzle<W, H, T>> for BoxPuzzle<T> {
+    fn eq(&self, other: &StackPuzzle<W, H, T>) -> bool {
+        self.shape() == other.shape() && self.iter().eq(other.iter())
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: Piece> PartialEq<NdArrayPuzzle<T>> for BoxPuzzle<T> {
+    fn eq(&self, other: &NdArrayPuzzle<T>) -> bool {
+        self.shape() == other.shape() && self.iter().eq(other.iter())
+    }
+}
+
+impl<const W: usize, const H: usize, T: Piece> From<StackPuzzle<W, H, T>> for BoxPuzzle<T> {
+    fn from(puzzle: StackPuzzle<W, H, T>) -> Self {
+        Self {
+            inner: puzzle.iter().cloned().collect(),
+            width: W,
+        }
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<T: Piece> From<NdArrayPuzzle<T>> for BoxPuzzle<T> {
+    fn from(puzzle: NdArrayPuzzle<T>) -> Self {
+        Self {
+            inner: puzzle.iter().cloned().collect(),
+            width: puzzle.shape().0,
+        }
+    }
+}
+
+// shape first, then the pieces in row-major order; the same for every implementation
+impl<T: Piece> Ord for BoxPuzzle<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Ord::cmp(&self.shape(), &other.shape()).then_with(|| self.inner.cmp(&other.inner))
+    }
+}
+
+impl<T: Piece> PartialOrd for BoxPuzzle<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Piece + Hash> Hash for BoxPuzzle<T> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.shape().hash(state);
+        self.iter().for_each(|piece| piece.hash(state));
+    }
+}
 
 impl<T: Piece> Puzzle<T> for BoxPuzzle<T> {
-    // type Iter<'a> = Iter<'a, T> where T: 'a;
-    // fn iter(&self) -> Self::Iter<'_> {
-    //     Iter {
-    //         inner:self.inner.iter()
-    //     }
-    // }
+    type Iter<'a>
+        = core::slice::Iter<'a, T>
+    where
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.inner.iter()
+    }
 
     #[inline]
     fn shape(&self) -> (usize, usize) {
@@ -182,7 +242,7 @@ impl<T: Piece> Puzzle<T> for BoxPuzzle<T> {
 
 impl<T: Piece + Display> Display for BoxPuzzle<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let digits = ((self.inner.len() - 1) as f32).log10() as usize + 1;
+        let digits = (self.inner.len() - 1).checked_ilog10().unwrap_or(0) as usize + 1;
         for row in self.inner.chunks(self.width) {
             for piece in row {
                 if piece.is_zero() {
@@ -199,67 +259,60 @@ impl<T: Piece + Display> Display for BoxPuzzle<T> {
 
 impl<T: Piece> BoxPuzzle<T> {
     pub fn random_with_rng(rng: &mut (impl Rng + ?Sized), (width, height): (usize, usize)) -> Self {
-        let len = width * height;
-        let mut pieces: Vec<T> = (1_usize..len)
-            .chain(once(0))
-            .map(num::cast)
-            .collect::<Option<_>>()
-            .expect("could not cast pieces to usize");
-
-        pieces[..len - 1].shuffle(rng);
-
-        if !is_solvable(&pieces, width) {
-            pieces.swap(0, 1);
-        }
-
-        let empty_idx = rng.gen_range(0..len);
-        let empty_pos = (empty_idx % width, empty_idx / width);
-
-        match (width - 1 - empty_pos.0) + (height - 1 - empty_pos.1) {
-            0 => {}
-            d if d.is_odd() => {
-                swap!(&mut pieces[empty_idx], &mut pieces[len - 1]);
-            }
-            _ => {
-                rotate!(
-                    &mut pieces[empty_idx],
-                    &mut pieces[len - 2],
-                    &mut pieces[len - 1]
-                );
-            }
-        }
-
+        let mut pieces = vec![T::zero(); width * height];
+        random_pieces(rng, &mut pieces, width, &mut vec![false; width * height]);
         Self {
             inner: pieces.into_boxed_slice(),
             width,
         }
     }
 
+    #[cfg(feature = "std")]
     pub fn random((width, height): (usize, usize)) -> Self {
         Self::random_with_rng(&mut rand::thread_rng(), (width, height))
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &T> {
-        self.inner.iter()
-    }
+    /// Builds a puzzle from its pieces, row by row, which must be `0..width * height` in any
+    /// order.
+    pub fn from_pieces(
+        (width, height): (usize, usize),
+        pieces: impl Into<Box<[T]>>,
+    ) -> Result<Self, ConversionError> {
+        let inner = pieces.into();
+        let len = width * height;
+        if width == 0 || inner.len() != len {
+            return Err(ConversionError::InvalidPieces);
+        }
 
-    pub fn iter_indexed(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
-        self.inner
-            .iter()
-            .enumerate()
-            .map(move |(idx, piece)| ((idx % self.width, idx / self.width), piece))
-    }
+        let mut seen = vec![false; len];
+        for piece in inner.iter() {
+            match num::cast::<_, usize>(piece.clone()) {
+                Some(piece) if piece < len && !seen[piece] => seen[piece] = true,
+                _ => return Err(ConversionError::InvalidPieces),
+            }
+        }
 
-    pub fn is_solved(&self) -> bool {
-        let iter_current = self.inner.iter();
-        let iter_solved = (1..self.inner.len()).chain(once(0));
+        Ok(Self { inner, width })
+    }
 
-        for (current, solved) in iter_current.zip(iter_solved) {
-            if num::cast(current.clone()) != Some(solved) {
-                return false;
-            }
+    pub fn cast<U: Piece + From<T>>(self) -> BoxPuzzle<U> {
+        BoxPuzzle {
+            inner: self.inner.into_vec().into_iter().map(Into::into).collect(),
+            width: self.width,
         }
+    }
 
-        true
+    pub fn try_cast<U: Piece>(self) -> Result<BoxPuzzle<U>, ConversionError> {
+        let inner = self
+            .iter_indexed()
+            .map(|(position, piece)| {
+                num::cast(piece.clone()).ok_or(ConversionError::PieceOutOfRange { position })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(BoxPuzzle {
+            inner,
+            width: self.width,
+        })
     }
 }
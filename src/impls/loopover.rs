@@ -0,0 +1,591 @@
+use rand::{seq::SliceRandom, Rng};
+
+use core::fmt::{self, Display};
+use core::ops::Index;
+use core::str::FromStr;
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use crate::{ConversionError, IterIndexed, NotationError, Piece, Position};
+
+/// A Loopover board: no empty piece, and every move rotates a whole row or column, with the
+/// tile pushed off one end coming back on the other.
+///
+/// Pieces are `1..=width * height` and belong in order from the top left.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Loopover<T: Piece> {
+    inner: Box<[T]>,
+    width: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Line {
+    /// The row `y`, counting from the top.
+    Row(usize),
+    /// The column `x`, counting from the left.
+    Column(usize),
+}
+
+/// One move of a [`Loopover`]: rows rotate rightwards and columns downwards by a positive
+/// `amount`.
+///
+/// In notation, a move is the index of the line, a letter for where it goes (`R`, `L`, `D` or
+/// `U`) and the amount if it is not 1: `0R`, `3U2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rotation {
+    pub line: Line,
+    pub amount: isize,
+}
+
+impl Rotation {
+    /// Parses moves separated by whitespace.
+    pub fn parse_sequence(notation: &str) -> Result<Vec<Self>, NotationError> {
+        notation
+            .split_whitespace()
+            .enumerate()
+            .map(|(index, token)| {
+                token
+                    .parse()
+                    .map_err(|_| NotationError::InvalidMove { index })
+            })
+            .collect()
+    }
+}
+
+impl Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (index, letter) = match (self.line, self.amount < 0) {
+            (Line::Row(y), false) => (y, 'R'),
+            (Line::Row(y), true) => (y, 'L'),
+            (Line::Column(x), false) => (x, 'D'),
+            (Line::Column(x), true) => (x, 'U'),
+        };
+        match self.amount.unsigned_abs() {
+            1 => write!(f, "{index}{letter}"),
+            amount => write!(f, "{index}{letter}{amount}"),
+        }
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = NotationError::InvalidMove { index: 0 };
+
+        let split = s.find(|c: char| !c.is_ascii_digit()).ok_or(invalid)?;
+        let (index, rest) = s.split_at(split);
+        let index = index.parse().map_err(|_| invalid)?;
+
+        let mut chars = rest.chars();
+        let letter = chars.next().ok_or(invalid)?;
+        let amount = match chars.as_str() {
+            "" => 1,
+            amount if amount.bytes().all(|b| b.is_ascii_digit()) => {
+                amount.parse::<isize>().map_err(|_| invalid)?
+            }
+            _ => return Err(invalid),
+        };
+
+        let (line, amount) = match letter.to_ascii_uppercase() {
+            'R' => (Line::Row(index), amount),
+            'L' => (Line::Row(index), -amount),
+            'D' => (Line::Column(index), amount),
+            'U' => (Line::Column(index), -amount),
+            _ => return Err(invalid),
+        };
+        Ok(Self { line, amount })
+    }
+}
+
+impl<T: Piece + Display> Display for Loopover<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = (self.inner.len() as f32).log10() as usize + 1;
+        for row in self.inner.chunks(self.width) {
+            for piece in row {
+                write!(f, "{: >digits$} ", piece)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Piece> Index<(usize, usize)> for Loopover<T> {
+    type Output = T;
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.inner[y * self.width + x]
+    }
+}
+
+impl<T: Piece> Index<Position> for Loopover<T> {
+    type Output = T;
+    fn index(&self, Position { x, y }: Position) -> &Self::Output {
+        &self[(x, y)]
+    }
+}
+
+impl<T: Piece> Loopover<T> {
+    pub fn solved((width, height): (usize, usize)) -> Self {
+        assert!(width > 0 && height > 0, "a board needs a cell");
+        let pieces = (1..=width * height)
+            .map(num::cast)
+            .collect::<Option<_>>()
+            .expect("could not cast pieces to usize");
+
+        Self {
+            inner: pieces,
+            width,
+        }
+    }
+
+    /// Builds a board from its pieces, row by row, which must be `1..=width * height` in any
+    /// order.
+    pub fn from_pieces(
+        (width, height): (usize, usize),
+        pieces: impl Into<Box<[T]>>,
+    ) -> Result<Self, ConversionError> {
+        let inner = pieces.into();
+        let len = width * height;
+        if width == 0 || inner.len() != len {
+            return Err(ConversionError::InvalidPieces);
+        }
+
+        let mut seen = vec![false; len + 1];
+        for piece in inner.iter() {
+            match num::cast::<_, usize>(piece.clone()) {
+                Some(piece) if (1..=len).contains(&piece) && !seen[piece] => seen[piece] = true,
+                _ => return Err(ConversionError::InvalidPieces),
+            }
+        }
+
+        Ok(Self { inner, width })
+    }
+
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized), (width, height): (usize, usize)) -> Self {
+        let mut board = Self::solved((width, height));
+
+        if width == 1 || height == 1 {
+            // a single line only ever rotates
+            let len = board.inner.len();
+            board.inner.rotate_left(rng.gen_range(0..len));
+            return board;
+        }
+
+        board.inner.shuffle(rng);
+        if !board.is_solvable() {
+            board.inner.swap(0, 1);
+        }
+        board
+    }
+
+    pub fn random((width, height): (usize, usize)) -> Self {
+        Self::random_with_rng(&mut rand::thread_rng(), (width, height))
+    }
+
+    #[inline]
+    pub fn shape(&self) -> (usize, usize) {
+        (self.width, self.inner.len() / self.width)
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.inner.iter()
+    }
+
+    pub fn iter_indexed(&self) -> IterIndexed<core::slice::Iter<'_, T>> {
+        IterIndexed::new(self.iter(), self.width)
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.iter()
+            .zip(1..)
+            .all(|(current, solved)| num::cast(current.clone()) == Some(solved))
+    }
+
+    /// Rotates a row or column, returning how many tiles moved, or `None` if there is no such
+    /// line.
+    pub fn rotate(&mut self, rotation: Rotation) -> Option<usize> {
+        rotate(&mut self.inner, self.width, rotation)
+    }
+
+    /// Whether the board can be solved at all.
+    ///
+    /// A rotation of a line of even length is an odd permutation, so with an even side every
+    /// arrangement can be solved, and with two odd sides only even permutations can. A single
+    /// row or column can only ever be rotated.
+    pub fn is_solvable(&self) -> bool {
+        let (width, height) = self.shape();
+        let cells = self.cells();
+
+        if width == 1 || height == 1 {
+            let len = cells.len();
+            return cells
+                .iter()
+                .enumerate()
+                .all(|(idx, &piece)| (piece + len - idx) % len == cells[0]);
+        }
+
+        width % 2 == 0 || height % 2 == 0 || parity(&cells) == 0
+    }
+
+    /// Finds a solution, far from the shortest one, or returns `None` if there is none.
+    ///
+    /// After fixing the parity if needed, tiles go in one at a time, row by row, each with a
+    /// commutator that cycles three cells and leaves the rest alone.
+    pub fn solve(&self) -> Option<Vec<Rotation>> {
+        if !self.is_solvable() {
+            return None;
+        }
+
+        let (width, height) = self.shape();
+        let mut board = Board {
+            width,
+            height,
+            cells: self.cells(),
+        };
+        let mut moves = Vec::new();
+
+        if width == 1 || height == 1 {
+            let line = match width {
+                1 => Line::Column(0),
+                _ => Line::Row(0),
+            };
+            moves.push(Rotation {
+                line,
+                amount: board.cells[0] as isize,
+            });
+            return Some(simplify(moves, (width, height)));
+        }
+
+        if parity(&board.cells) == 1 {
+            let line = match width % 2 {
+                0 => Line::Row(0),
+                _ => Line::Column(0),
+            };
+            board.apply(&mut moves, Rotation { line, amount: 1 });
+        }
+
+        for goal in 0..board.cells.len() {
+            let from = board
+                .cells
+                .iter()
+                .position(|&piece| piece == goal)
+                .expect("potential BUG: could not find a piece");
+            if from == goal {
+                continue;
+            }
+
+            let (setup, corner) = board.setup(from, goal);
+            for &rotation in &setup {
+                board.apply(&mut moves, rotation);
+            }
+            for (line, amount) in [
+                (Line::Row(corner.1), 1),
+                (Line::Column(corner.0), 1),
+                (Line::Row(corner.1), -1),
+                (Line::Column(corner.0), -1),
+            ] {
+                board.apply(&mut moves, Rotation { line, amount });
+            }
+            for &rotation in setup.iter().rev() {
+                board.apply(
+                    &mut moves,
+                    Rotation {
+                        amount: -rotation.amount,
+                        ..rotation
+                    },
+                );
+            }
+        }
+
+        debug_assert!(board.cells.iter().enumerate().all(|(idx, &p)| idx == p));
+        Some(simplify(moves, (width, height)))
+    }
+
+    // where every piece belongs, counting from 0
+    fn cells(&self) -> Vec<usize> {
+        self.iter()
+            .map(|piece| num::cast::<_, usize>(piece.clone()).map(|piece| piece - 1))
+            .collect::<Option<_>>()
+            .expect("could not cast pieces to usize")
+    }
+}
+
+fn rotate<T: Clone>(cells: &mut [T], width: usize, rotation: Rotation) -> Option<usize> {
+    let height = cells.len() / width;
+    let (line, length) = match rotation.line {
+        Line::Row(y) if y < height => {
+            ((0..width).map(|x| y * width + x).collect::<Vec<_>>(), width)
+        }
+        Line::Column(x) if x < width => ((0..height).map(|y| y * width + x).collect(), height),
+        _ => return None,
+    };
+
+    let mut pieces = line
+        .iter()
+        .map(|&idx| cells[idx].clone())
+        .collect::<Vec<_>>();
+    pieces.rotate_right(rotation.amount.rem_euclid(length as isize) as usize);
+    for (idx, piece) in line.into_iter().zip(pieces) {
+        cells[idx] = piece;
+    }
+
+    match rotation.amount % length as isize {
+        0 => Some(0),
+        _ => Some(length),
+    }
+}
+
+// 0 for even permutations, 1 for odd ones
+fn parity(cells: &[usize]) -> usize {
+    let mut seen = vec![false; cells.len()];
+    let mut parity = 0;
+    for start in 0..cells.len() {
+        let mut length = 0;
+        let mut idx = start;
+        while !seen[idx] {
+            seen[idx] = true;
+            idx = cells[idx];
+            length += 1;
+        }
+        parity += length.max(1) - 1;
+    }
+    parity % 2
+}
+
+// merges moves of the same line in a row, and takes the shorter way round
+fn simplify(moves: Vec<Rotation>, (width, height): (usize, usize)) -> Vec<Rotation> {
+    let normalize = |rotation: Rotation| {
+        let length = match rotation.line {
+            Line::Row(_) => width,
+            Line::Column(_) => height,
+        } as isize;
+        let amount = rotation.amount.rem_euclid(length);
+        Rotation {
+            amount: if amount > length / 2 {
+                amount - length
+            } else {
+                amount
+            },
+            ..rotation
+        }
+    };
+
+    let mut simplified: Vec<Rotation> = Vec::with_capacity(moves.len());
+    for rotation in moves {
+        match simplified.last_mut() {
+            Some(last) if last.line == rotation.line => {
+                *last = normalize(Rotation {
+                    amount: last.amount + rotation.amount,
+                    ..rotation
+                });
+                if last.amount == 0 {
+                    simplified.pop();
+                }
+            }
+            _ => {
+                let rotation = normalize(rotation);
+                if rotation.amount != 0 {
+                    simplified.push(rotation);
+                }
+            }
+        }
+    }
+    simplified
+}
+
+struct Board {
+    width: usize,
+    height: usize,
+    cells: Vec<usize>,
+}
+
+impl Board {
+    fn apply(&mut self, moves: &mut Vec<Rotation>, rotation: Rotation) {
+        rotate(&mut self.cells, self.width, rotation);
+        moves.push(rotation);
+    }
+
+    // where the tile at `position` goes with `rotation`
+    fn moved(&self, (x, y): (usize, usize), rotation: Rotation) -> (usize, usize) {
+        let wrap = |value: usize, length: usize| {
+            (value as isize + rotation.amount).rem_euclid(length as isize) as usize
+        };
+        match rotation.line {
+            Line::Row(row) if row == y => (wrap(x, self.width), y),
+            Line::Column(column) if column == x => (x, wrap(y, self.height)),
+            _ => (x, y),
+        }
+    }
+
+    // moves that bring the tile at `from` and the cell `goal` where the commutator at the
+    // returned corner moves one into the other, with a third cell not solved yet
+    //
+    // the commutator moves the left of the corner to the corner, the corner up and the cell
+    // above the corner to its left; conjugated by the setup, it moves `from` to `goal`
+    fn setup(&self, from: usize, goal: usize) -> (Vec<Rotation>, (usize, usize)) {
+        let (width, height) = (self.width, self.height);
+        let position = |idx: usize| (idx % width, idx / width);
+        let index = |(x, y): (usize, usize)| y * width + x;
+        let left = |(x, y): (usize, usize)| ((x + width - 1) % width, y);
+        let up = |(x, y): (usize, usize)| (x, (y + height - 1) % height);
+        let right = |(x, y): (usize, usize)| ((x + 1) % width, y);
+        let down = |(x, y): (usize, usize)| (x, (y + 1) % height);
+
+        let mut units = Vec::new();
+        for y in 0..height {
+            units.push(Rotation {
+                line: Line::Row(y),
+                amount: 1,
+            });
+            units.push(Rotation {
+                line: Line::Row(y),
+                amount: -1,
+            });
+        }
+        for x in 0..width {
+            units.push(Rotation {
+                line: Line::Column(x),
+                amount: 1,
+            });
+            units.push(Rotation {
+                line: Line::Column(x),
+                amount: -1,
+            });
+        }
+
+        let start = (position(from), position(goal));
+        let mut parents = HashMap::from([(start, None)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(state) = queue.pop_front() {
+            let (a, b) = state;
+            // (corner, the third cell) for every way the pair fits the commutator
+            let fits = [
+                (right(a) == b).then_some((b, up(b))),
+                (up(a) == b).then_some((a, left(a))),
+                (left(down(a)) == b).then_some((down(a), down(a))),
+            ];
+
+            let path = || {
+                let mut path: Vec<Rotation> = Vec::new();
+                let mut current = state;
+                while let Some(&Some((previous, rotation))) = parents.get(&current) {
+                    path.push(rotation);
+                    current = previous;
+                }
+                path.reverse();
+                path
+            };
+
+            for (corner, third) in fits.into_iter().flatten() {
+                let path = path();
+                // where the third cell was before the setup
+                let third = path.iter().rev().fold(third, |position, &rotation| {
+                    self.moved(
+                        position,
+                        Rotation {
+                            amount: -rotation.amount,
+                            ..rotation
+                        },
+                    )
+                });
+                if index(third) > goal {
+                    return (path, corner);
+                }
+            }
+
+            for &rotation in &units {
+                let next = (self.moved(a, rotation), self.moved(b, rotation));
+                if let Entry::Vacant(entry) = parents.entry(next) {
+                    entry.insert(Some((state, rotation)));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        unreachable!("potential BUG: no setup moves {from} to {goal}")
+    }
+}
+
+#[test]
+fn loopover_works() {
+    let moves = Rotation::parse_sequence("0R 2u3  1L\t4D").unwrap();
+    assert_eq!(
+        moves,
+        [
+            Rotation {
+                line: Line::Row(0),
+                amount: 1
+            },
+            Rotation {
+                line: Line::Column(2),
+                amount: -3
+            },
+            Rotation {
+                line: Line::Row(1),
+                amount: -1
+            },
+            Rotation {
+                line: Line::Column(4),
+                amount: 1
+            },
+        ]
+    );
+    assert_eq!(
+        moves.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        ["0R", "2U3", "1L", "4D"]
+    );
+    assert_eq!(
+        Rotation::parse_sequence("0R R0"),
+        Err(NotationError::InvalidMove { index: 1 })
+    );
+    assert!("1X".parse::<Rotation>().is_err());
+    assert!("1R-2".parse::<Rotation>().is_err());
+
+    // 1 2 3    3 8 2
+    // 4 5 6 -> 4 1 6
+    // 7 8 9    7 5 9
+    let mut board = Loopover::<u8>::solved((3, 3));
+    assert_eq!(board.rotate(moves[0]), Some(3));
+    assert_eq!(board.rotate(moves[3]), None);
+    assert_eq!(
+        "1D".parse().map(|rotation| board.rotate(rotation)),
+        Ok(Some(3))
+    );
+    assert_eq!(
+        board.iter().copied().collect::<Vec<_>>(),
+        [3, 8, 2, 4, 1, 6, 7, 5, 9]
+    );
+    assert_eq!(
+        "1U".parse().map(|rotation| board.rotate(rotation)),
+        Ok(Some(3))
+    );
+    assert_eq!(
+        "0L4".parse().map(|rotation| board.rotate(rotation)),
+        Ok(Some(3))
+    );
+    assert!(board.is_solved());
+    assert_eq!(board.to_string(), "1 2 3 \n4 5 6 \n7 8 9 \n");
+
+    // a swap is odd: fine with an even side, impossible with two odd ones
+    let swapped = Loopover::<u8>::from_pieces((3, 3), vec![2, 1, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+    assert!(!swapped.is_solvable());
+    assert_eq!(swapped.solve(), None);
+    let swapped = Loopover::<u8>::from_pieces((4, 3), (1..=12).rev().collect::<Vec<_>>()).unwrap();
+    assert!(swapped.is_solvable());
+    let line = Loopover::<u8>::from_pieces((1, 4), vec![3, 4, 1, 2]).unwrap();
+    assert!(line.is_solvable());
+    assert!(!Loopover::<u8>::from_pieces((4, 1), vec![3, 4, 2, 1])
+        .unwrap()
+        .is_solvable());
+
+    let mut rng = rand::thread_rng();
+    for shape in [(1, 4), (2, 2), (3, 3), (5, 5), (6, 4)] {
+        let mut board = Loopover::<u8>::random_with_rng(&mut rng, shape);
+        let solution = board.solve().unwrap();
+        for rotation in solution {
+            assert!(rotation.amount != 0);
+            board.rotate(rotation);
+        }
+        assert!(board.is_solved());
+    }
+}
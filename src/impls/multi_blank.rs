@@ -0,0 +1,355 @@
+use rand::{seq::SliceRandom, Rng};
+
+use core::fmt::{self, Display};
+use core::ops::Index;
+
+use crate::{is_solvable, ConversionError, Direction, LegalMoves, Piece, Position, Puzzle};
+
+/// A sliding puzzle with any number of empty pieces, all of them `T::zero()`.
+///
+/// Tiles are `1..=width * height - blanks` and belong in order from the top left, with the
+/// blanks after them in the bottom right. [`slide_from`](Puzzle::slide_from) infers which blank
+/// to slide into, [`slide_into`](Self::slide_into) is told, and
+/// [`slide_towards`](Puzzle::slide_towards) moves the first blank in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MultiBlankPuzzle<T: Piece> {
+    inner: Box<[T]>,
+    width: usize,
+}
+
+impl<T: Piece + Display> Display for MultiBlankPuzzle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = ((self.inner.len() - 1) as f32).log10() as usize + 1;
+        for row in self.inner.chunks(self.width) {
+            for piece in row {
+                if piece.is_zero() {
+                    write!(f, "{: >digits$} ", "")?;
+                } else {
+                    write!(f, "{: >digits$} ", piece)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Piece> Index<(usize, usize)> for MultiBlankPuzzle<T> {
+    type Output = T;
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.inner[y * self.width + x]
+    }
+}
+
+impl<T: Piece> Index<Position> for MultiBlankPuzzle<T> {
+    type Output = T;
+    fn index(&self, Position { x, y }: Position) -> &Self::Output {
+        &self[(x, y)]
+    }
+}
+
+impl<T: Piece> Puzzle<T> for MultiBlankPuzzle<T> {
+    type Iter<'a>
+        = core::slice::Iter<'a, T>
+    where
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.inner.iter()
+    }
+
+    #[inline]
+    fn shape(&self) -> (usize, usize) {
+        (self.width, self.inner.len() / self.width)
+    }
+
+    fn index_of(&self, value: T) -> Option<(usize, usize)> {
+        self.inner
+            .iter()
+            .position(|x| *x == value)
+            .map(|idx| (idx % self.width, idx / self.width))
+    }
+
+    fn is_solved(&self) -> bool {
+        let tiles = self.inner.len() - self.blanks().count();
+        let solved = (1..=tiles).chain(core::iter::repeat(0));
+
+        self.iter()
+            .zip(solved)
+            .all(|(current, solved)| num::cast(current.clone()) == Some(solved))
+    }
+
+    /// Slides into the closest blank in the same row or column, as long as there is only one.
+    fn slide_from(&mut self, from: (usize, usize)) -> Option<usize> {
+        let (width, height) = self.shape();
+        if !matches!(from, (x, y) if x < width && y < height) {
+            return None;
+        }
+        if self[from].is_zero() {
+            return Some(0);
+        }
+
+        // the first blank in every direction, which has nothing but tiles up to it
+        let mut closest: Option<((usize, usize), usize)> = None;
+        let mut ambiguous = false;
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let blank = (1..)
+                .map_while(|distance: isize| {
+                    let x = from.0.checked_add_signed(dx * distance)?;
+                    let y = from.1.checked_add_signed(dy * distance)?;
+                    (x < width && y < height).then_some(((x, y), distance as usize))
+                })
+                .find(|&(position, _)| self[position].is_zero());
+
+            match (blank, closest) {
+                (Some((_, distance)), Some((_, shortest))) if distance == shortest => {
+                    ambiguous = true;
+                }
+                (Some((_, distance)), Some((_, shortest))) if distance > shortest => {}
+                (Some(blank), _) => {
+                    closest = Some(blank);
+                    ambiguous = false;
+                }
+                (None, _) => {}
+            }
+        }
+
+        match (closest, ambiguous) {
+            (Some((blank, _)), false) => self.slide_into(from, blank),
+            _ => None,
+        }
+    }
+
+    fn slide_towards(&mut self, direction: Direction, distance: usize) -> Option<usize> {
+        let blank = self
+            .blanks()
+            .next()
+            .expect("potential BUG: could not find an empty piece");
+
+        let direction: (isize, isize) = direction.into();
+        let distance = isize::try_from(distance).ok()?;
+
+        self.slide_into(
+            (
+                blank.0.checked_add_signed(-direction.0 * distance)?,
+                blank.1.checked_add_signed(-direction.1 * distance)?,
+            ),
+            blank,
+        )
+    }
+
+    /// Slides into the first blank, like [`slide_towards`](Puzzle::slide_towards), stopping
+    /// short of any other blank.
+    fn legal_moves(&self) -> LegalMoves {
+        let blank = self
+            .blanks()
+            .next()
+            .expect("potential BUG: could not find an empty piece");
+        LegalMoves::until(self.shape(), blank, |position| self[position].is_zero())
+    }
+}
+
+impl<T: Piece> MultiBlankPuzzle<T> {
+    /// Builds a puzzle from its pieces, row by row: `1..=width * height - blanks` in any order,
+    /// and at least one `T::zero()` for the rest.
+    pub fn from_pieces(
+        (width, height): (usize, usize),
+        pieces: impl Into<Box<[T]>>,
+    ) -> Result<Self, ConversionError> {
+        let inner = pieces.into();
+        let len = width * height;
+        if width == 0 || inner.len() != len {
+            return Err(ConversionError::InvalidPieces);
+        }
+
+        let tiles = inner.iter().filter(|piece| !piece.is_zero()).count();
+        if tiles == len {
+            return Err(ConversionError::InvalidPieces);
+        }
+
+        let mut seen = vec![false; tiles + 1];
+        for piece in inner.iter().filter(|piece| !piece.is_zero()) {
+            match num::cast::<_, usize>(piece.clone()) {
+                Some(piece) if piece <= tiles && !seen[piece] => seen[piece] = true,
+                _ => return Err(ConversionError::InvalidPieces),
+            }
+        }
+
+        Ok(Self { inner, width })
+    }
+
+    /// The solved puzzle with `blanks` empty pieces, `1..=width * height` of them.
+    pub fn solved((width, height): (usize, usize), blanks: usize) -> Self {
+        let len = width * height;
+        assert!(
+            (1..=len).contains(&blanks),
+            "{blanks} blanks do not fit on a {width}x{height} board"
+        );
+
+        let pieces = (1..=len - blanks)
+            .chain(core::iter::repeat_n(0, blanks))
+            .map(num::cast)
+            .collect::<Option<_>>()
+            .expect("could not cast pieces to usize");
+
+        Self {
+            inner: pieces,
+            width,
+        }
+    }
+
+    pub fn random_with_rng(
+        rng: &mut (impl Rng + ?Sized),
+        (width, height): (usize, usize),
+        blanks: usize,
+    ) -> Self {
+        let mut puzzle = Self::solved((width, height), blanks);
+        puzzle.inner.shuffle(rng);
+
+        if width == 1 || height == 1 {
+            // tiles cannot pass each other on a single line
+            let mut tiles = 1_usize..;
+            for piece in puzzle.inner.iter_mut().filter(|piece| !piece.is_zero()) {
+                *piece = num::cast(tiles.next().unwrap()).expect("could not cast pieces to usize");
+            }
+        } else if !puzzle.is_solvable() {
+            let mut tiles = puzzle.inner.iter_mut().filter(|piece| !piece.is_zero());
+            let (Some(a), Some(b)) = (tiles.next(), tiles.next()) else {
+                unreachable!("potential BUG: a single tile is always solvable");
+            };
+            core::mem::swap(a, b);
+        }
+
+        puzzle
+    }
+
+    pub fn random((width, height): (usize, usize), blanks: usize) -> Self {
+        Self::random_with_rng(&mut rand::thread_rng(), (width, height), blanks)
+    }
+
+    /// Where the empty pieces are, in row-major order.
+    pub fn blanks(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.iter_indexed()
+            .filter(|(_, piece)| piece.is_zero())
+            .map(|(position, _)| position)
+    }
+
+    /// Whether the puzzle can be solved at all.
+    ///
+    /// Two blanks or more can swap places with each other to fix the parity that decides this
+    /// with one, so then everything is solvable unless the board is a single line, on which
+    /// tiles can never pass each other.
+    pub fn is_solvable(&self) -> bool {
+        let (width, height) = self.shape();
+        if width == 1 || height == 1 {
+            return self
+                .iter()
+                .filter(|piece| !piece.is_zero())
+                .zip(1_usize..)
+                .all(|(piece, solved)| num::cast(piece.clone()) == Some(solved));
+        }
+
+        match self.blanks().count() {
+            1 => is_solvable(&self.inner, width),
+            _ => true,
+        }
+    }
+
+    /// Slides the tiles from `from` up to the blank at `blank`, which must be in the same row or
+    /// column with only tiles in between.
+    pub fn slide_into(&mut self, from: (usize, usize), blank: (usize, usize)) -> Option<usize> {
+        let (width, height) = self.shape();
+        let inside = |(x, y): (usize, usize)| x < width && y < height;
+        if !inside(from) || !inside(blank) || !self[blank].is_zero() {
+            return None;
+        }
+        if from == blank {
+            return Some(0);
+        }
+        if from.0 != blank.0 && from.1 != blank.1 {
+            return None;
+        }
+
+        let distance = from.0.abs_diff(blank.0) + from.1.abs_diff(blank.1);
+        let step = (
+            (from.0 as isize - blank.0 as isize).signum(),
+            (from.1 as isize - blank.1 as isize).signum(),
+        );
+        // from the blank to `from`
+        let line = (0..=distance as isize)
+            .map(|n| {
+                let x = blank.0.wrapping_add_signed(step.0 * n);
+                let y = blank.1.wrapping_add_signed(step.1 * n);
+                y * width + x
+            })
+            .collect::<Vec<_>>();
+
+        if line[1..].iter().any(|&idx| self.inner[idx].is_zero()) {
+            return None;
+        }
+        for pair in line.windows(2) {
+            self.inner.swap(pair[0], pair[1]);
+        }
+
+        Some(distance)
+    }
+}
+
+#[test]
+fn multi_blank_works() {
+    // 1 2 3
+    // 4 5 _
+    // 7 _ 6
+    let mut puzzle =
+        MultiBlankPuzzle::from_pieces((3, 3), vec![1, 2, 3, 4, 5, 0, 7, 0, 6]).unwrap();
+    assert_eq!(puzzle.blanks().collect::<Vec<_>>(), [(2, 1), (1, 2)]);
+    assert!(!puzzle.is_solved());
+
+    // 6 is as close to both blanks
+    assert_eq!(puzzle.slide_from((2, 2)), None);
+    assert_eq!(puzzle.slide_from((3, 2)), None);
+    assert_eq!(puzzle.slide_from((0, 0)), None);
+    assert_eq!(puzzle.slide_from((1, 2)), Some(0));
+    assert_eq!(puzzle.slide_into((2, 0), (2, 2)), None);
+    assert_eq!(puzzle.slide_into((0, 0), (1, 2)), None);
+    assert_eq!(puzzle.slide_into((2, 2), (2, 1)), Some(1));
+    assert!(puzzle.is_solved());
+    assert_eq!(puzzle, MultiBlankPuzzle::solved((3, 3), 2));
+    assert_eq!(puzzle.to_string(), "1 2 3 \n4 5 6 \n7     \n");
+
+    // tiles in between move along, and the closer blank wins
+    assert_eq!(puzzle.slide_from((1, 0)), Some(2));
+    assert_eq!(puzzle.slide_from((1, 2)), Some(1));
+    assert_eq!(
+        puzzle.iter().copied().collect::<Vec<u8>>(),
+        [1, 0, 3, 4, 2, 6, 7, 0, 5]
+    );
+    assert_eq!(puzzle.slide_towards(Direction::Up, 1), Some(1));
+    assert_eq!(
+        puzzle.iter().copied().collect::<Vec<u8>>(),
+        [1, 2, 3, 4, 0, 6, 7, 0, 5]
+    );
+
+    assert_eq!(
+        MultiBlankPuzzle::<u8>::from_pieces((2, 2), vec![1, 2, 3, 4]),
+        Err(ConversionError::InvalidPieces)
+    );
+    assert_eq!(
+        MultiBlankPuzzle::<u8>::from_pieces((2, 2), vec![1, 3, 0, 0]),
+        Err(ConversionError::InvalidPieces)
+    );
+
+    // two blanks make any board solvable, but not any line
+    let swapped = MultiBlankPuzzle::<u8>::from_pieces((2, 2), vec![2, 1, 0, 0]).unwrap();
+    assert!(swapped.is_solvable());
+    let swapped = MultiBlankPuzzle::<u8>::from_pieces((3, 1), vec![2, 0, 1]).unwrap();
+    assert!(!swapped.is_solvable());
+    let swapped = MultiBlankPuzzle::<u8>::from_pieces((3, 2), vec![2, 1, 3, 4, 5, 0]).unwrap();
+    assert!(!swapped.is_solvable());
+
+    let mut rng = rand::thread_rng();
+    for (shape, blanks) in [((4, 4), 1), ((4, 4), 3), ((1, 6), 2), ((5, 2), 10)] {
+        let puzzle = MultiBlankPuzzle::<u8>::random_with_rng(&mut rng, shape, blanks);
+        assert_eq!(puzzle.blanks().count(), blanks);
+        assert!(puzzle.is_solvable());
+    }
+}
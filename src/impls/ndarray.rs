@@ -1,15 +1,14 @@
-use ndarray::{array, s, Array2};
-use num::Integer;
-use omniswap::{rotate, swap};
-use rand::{seq::SliceRandom, Rng};
+use ndarray::{array, s, Array2, Ix2};
+use rand::Rng;
 
+use core::cmp::Ordering;
 use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
 use core::hint::unreachable_unchecked;
 use core::mem;
 use core::ops::Index;
-use core::{cmp::Ordering, iter::once};
 
-use crate::{is_solvable, Piece, Puzzle};
+use crate::{random_pieces, BoxPuzzle, ConversionError, Piece, Position, Puzzle, StackPuzzle};
 
 #[derive(Clone)]
 pub struct NdArrayPuzzle<T: Piece> {
@@ -56,11 +55,80 @@ impl<T: Piece + Debug> Debug for NdArrayPuzzle<T> {
 impl<T: Piece> Index<(usize, usize)> for NdArrayPuzzle<T> {
     type Output = T;
     fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
-        self.inner.index((x, y))
+        self.inner.index((y, x))
+    }
+}
+
+impl<T: Piece> Index<Position> for NdArrayPuzzle<T> {
+    type Output = T;
+    fn index(&self, Position { x, y }: Position) -> &Self::Output {
+        &self[(x, y)]
+    }
+}
+
+impl<T: Piece> PartialEq for NdArrayPuzzle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: Piece> Eq for NdArrayPuzzle<T> {}
+
+impl<T: Piece> PartialEq<BoxPuzzle<T>> for NdArrayPuzzle<T> {
+    fn eq(&self, other: &BoxPuzzle<T>) -> bool {
+        other == self
+    }
+}
+
+impl<const W: usize, const H: usize, T: Piece> PartialEq<StackPuzzle<W, H, T>>
+    for NdArrayPuzzle<T>
+{
+    fn eq(&self, other: &StackPuzzle<W, H, T>) -> bool {
+        other == self
+    }
+}
+
+impl<T: Piece> From<BoxPuzzle<T>> for NdArrayPuzzle<T> {
+    fn from(puzzle: BoxPuzzle<T>) -> Self {
+        Self::from_puzzle(&puzzle)
+    }
+}
+
+impl<const W: usize, const H: usize, T: Piece> From<StackPuzzle<W, H, T>> for NdArrayPuzzle<T> {
+    fn from(puzzle: StackPuzzle<W, H, T>) -> Self {
+        Self::from_puzzle(&puzzle)
+    }
+}
+
+// shape first, then the pieces in row-major order; the same for every implementation
+impl<T: Piece> Ord for NdArrayPuzzle<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Ord::cmp(&self.shape(), &other.shape()).then_with(|| self.iter().cmp(other.iter()))
+    }
+}
+
+impl<T: Piece> PartialOrd for NdArrayPuzzle<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Piece + Hash> Hash for NdArrayPuzzle<T> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.shape().hash(state);
+        self.iter().for_each(|piece| piece.hash(state));
     }
 }
 
 impl<T: Piece> Puzzle<T> for NdArrayPuzzle<T> {
+    type Iter<'a>
+        = ndarray::iter::Iter<'a, T, Ix2>
+    where
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.inner.iter()
+    }
+
     fn shape(&self) -> (usize, usize) {
         let shape = self.inner.shape();
         (shape[1], shape[0])
@@ -188,36 +256,8 @@ impl<T: Piece + Display + Eq> Display for NdArrayPuzzle<T> {
 
 impl<T: Piece> NdArrayPuzzle<T> {
     pub fn random_with_rng(rng: &mut (impl Rng + ?Sized), (width, height): (usize, usize)) -> Self {
-        let len = width * height;
-        let mut pieces: Vec<T> = (1_usize..len)
-            .chain(once(0))
-            .map(num::cast)
-            .collect::<Option<_>>()
-            .expect("could not cast pieces to usize");
-
-        pieces[..len - 1].shuffle(rng);
-
-        if !is_solvable(&pieces, width) {
-            pieces.swap(0, 1);
-        }
-
-        let empty_idx = rng.gen_range(0..len);
-        let empty_pos = (empty_idx % width, empty_idx / width);
-
-        match (width - 1 - empty_pos.0) + (height - 1 - empty_pos.1) {
-            0 => {}
-            d if d.is_odd() => {
-                swap!(&mut pieces[empty_idx], &mut pieces[len - 1]);
-            }
-            _ => {
-                rotate!(
-                    &mut pieces[empty_idx],
-                    &mut pieces[len - 2],
-                    &mut pieces[len - 1]
-                );
-            }
-        }
-
+        let mut pieces = vec![T::zero(); width * height];
+        random_pieces(rng, &mut pieces, width, &mut vec![false; width * height]);
         Self {
             inner: Array2::from_shape_vec((height, width), pieces).unwrap(),
         }
@@ -226,4 +266,30 @@ impl<T: Piece> NdArrayPuzzle<T> {
     pub fn random((width, height): (usize, usize)) -> Self {
         Self::random_with_rng(&mut rand::thread_rng(), (width, height))
     }
+
+    pub fn cast<U: Piece + From<T>>(self) -> NdArrayPuzzle<U> {
+        NdArrayPuzzle {
+            inner: self.inner.mapv(Into::into),
+        }
+    }
+
+    pub fn try_cast<U: Piece>(self) -> Result<NdArrayPuzzle<U>, ConversionError> {
+        let pieces = self
+            .iter_indexed()
+            .map(|(position, piece)| {
+                num::cast(piece.clone()).ok_or(ConversionError::PieceOutOfRange { position })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(NdArrayPuzzle {
+            inner: Array2::from_shape_vec(self.inner.raw_dim(), pieces).unwrap(),
+        })
+    }
+
+    fn from_puzzle<P: Puzzle<T>>(puzzle: &P) -> Self {
+        let (width, height) = puzzle.shape();
+        Self {
+            inner: Array2::from_shape_fn((height, width), |(y, x)| puzzle[(x, y)].clone()),
+        }
+    }
 }
@@ -1,10 +1,18 @@
 use core::cmp::Ordering;
 use core::fmt::{self, Debug};
+use core::hash::{Hash, Hasher};
 use core::hint::unreachable_unchecked;
+use core::iter::Flatten;
 use core::mem;
 use core::ops::Index;
 
-use crate::{Piece, Puzzle};
+use rand::Rng;
+
+#[cfg(feature = "alloc")]
+use crate::BoxPuzzle;
+#[cfg(feature = "ndarray")]
+use crate::NdArrayPuzzle;
+use crate::{random_pieces, ConversionError, Piece, Position, Puzzle};
 
 #[derive(Clone)]
 pub struct StackPuzzle<const W: usize, const H: usize, T: Piece> {
@@ -52,15 +60,105 @@ impl<const W: usize, const H: usize, T: Piece + Debug> Debug for StackPuzzle<W,
 
 impl Default for StackPuzzle<4, 4, u8> {
     fn default() -> Self {
-        Self {
-            #[rustfmt::skip]
-            pieces: [
-                [1, 2, 3, 4],
-                [5, 6, 7, 8],
-                [9, 10, 11, 12],
-                [13, 14, 15, 0],
-            ],
+        Self::solved()
+    }
+}
+
+macro_rules! impl_const {
+    ($($t:ty),*) => {$(
+        impl<const W: usize, const H: usize> StackPuzzle<W, H, $t> {
+            /// The solved puzzle. Fails to compile in const contexts if the board is empty or
+            /// its pieces do not fit in the piece type.
+            pub const fn solved() -> Self {
+                assert!(W > 0 && H > 0, "a puzzle needs at least one cell");
+                assert!(W * H - 1 <= <$t>::MAX as usize, "pieces do not fit in the piece type");
+
+                let mut pieces = [[0; W]; H];
+                let mut idx = 0;
+                while idx < W * H {
+                    pieces[idx / W][idx % W] = ((idx + 1) % (W * H)) as $t;
+                    idx += 1;
+                }
+                Self { pieces }
+            }
+
+            /// [`Puzzle::is_solved`], in const contexts as well.
+            pub const fn is_solved(&self) -> bool {
+                let mut idx = 0;
+                while idx < W * H {
+                    if self.pieces[idx / W][idx % W] != ((idx + 1) % (W * H)) as $t {
+                        return false;
+                    }
+                    idx += 1;
+                }
+                true
+            }
         }
+    )*};
+}
+
+impl_const!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// A [`StackPuzzle`] from its rows, checked at compile time to be `0..width * height` in some
+/// order, which makes for exactly one empty piece.
+///
+/// ```
+/// let puzzle: jugo::StackPuzzle<3, 2, u8> = jugo::stack_puzzle![
+///     [1, 2, 3],
+///     [4, 0, 5],
+/// ];
+/// ```
+///
+/// ```compile_fail
+/// // two empty pieces
+/// let puzzle: jugo::StackPuzzle<3, 2, u8> = jugo::stack_puzzle![
+///     [1, 2, 3],
+///     [4, 0, 0],
+/// ];
+/// ```
+#[macro_export]
+macro_rules! stack_puzzle {
+    ($([$($piece:expr),+ $(,)?]),+ $(,)?) => {{
+        const _: () = $crate::validate_layout(&[$(&[$($piece as usize),+]),+]);
+        $crate::StackPuzzle::from_rows_unchecked([$([$($piece),+]),+])
+    }};
+}
+
+// what `stack_puzzle!` checks, panicking at compile time
+#[doc(hidden)]
+pub const fn validate_layout(rows: &[&[usize]]) {
+    assert!(!rows.is_empty(), "a puzzle needs at least one row");
+    let width = rows[0].len();
+    let len = width * rows.len();
+
+    let mut blanks = 0;
+    let mut idx = 0;
+    while idx < len {
+        assert!(
+            rows[idx / width].len() == width,
+            "every row has to be as long as the first"
+        );
+        let piece = rows[idx / width][idx % width];
+        assert!(piece < len, "pieces have to be below width * height");
+        if piece == 0 {
+            blanks += 1;
+        }
+        idx += 1;
+    }
+    assert!(blanks == 1, "there has to be exactly one empty piece");
+
+    // every piece is in range, so no duplicates makes a permutation
+    let mut idx = 0;
+    while idx < len {
+        let mut other = idx + 1;
+        while other < len {
+            assert!(
+                rows[idx / width][idx % width] != rows[other / width][other % width],
+                "pieces have to be distinct"
+            );
+            other += 1;
+        }
+        idx += 1;
     }
 }
 
@@ -71,7 +169,145 @@ impl<const W: usize, const H: usize, T: Piece> Index<(usize, usize)> for StackPu
     }
 }
 
+impl<const W: usize, const H: usize, T: Piece> Index<Position> for StackPuzzle<W, H, T> {
+    type Output = T;
+    fn index(&self, Position { x, y }: Position) -> &Self::Output {
+        &self[(x, y)]
+    }
+}
+
+impl<const W: usize, const H: usize, T: Piece> PartialEq for StackPuzzle<W, H, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.pieces == other.pieces
+    }
+}
+
+impl<const W: usize, const H: usize, T: Piece> Eq for StackPuzzle<W, H, T> {}
+
+#[cfg(feature = "alloc")]
+impl<const W: usize, const H: usize, T: Piece> PartialEq<BoxPuzzle<T>> for StackPuzzle<W, H, T> {
+    fn eq(&self, other: &BoxPuzzle<T>) -> bool {
+        other == self
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<const W: usize, const H: usize, T: Piece> PartialEq<NdArrayPuzzle<T>>
+    for StackPuzzle<W, H, T>
+{
+    fn eq(&self, other: &NdArrayPuzzle<T>) -> bool {
+        self.shape() == other.shape() && self.iter().eq(other.iter())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const W: usize, const H: usize, T: Piece> TryFrom<BoxPuzzle<T>> for StackPuzzle<W, H, T> {
+    type Error = ConversionError;
+    fn try_from(puzzle: BoxPuzzle<T>) -> Result<Self, Self::Error> {
+        Self::try_from_puzzle(&puzzle)
+    }
+}
+
+#[cfg(feature = "ndarray")]
+impl<const W: usize, const H: usize, T: Piece> TryFrom<NdArrayPuzzle<T>> for StackPuzzle<W, H, T> {
+    type Error = ConversionError;
+    fn try_from(puzzle: NdArrayPuzzle<T>) -> Result<Self, Self::Error> {
+        Self::try_from_puzzle(&puzzle)
+    }
+}
+
+// the shape is fixed by the type, so this is just the pieces in row-major order
+impl<const W: usize, const H: usize, T: Piece> Ord for StackPuzzle<W, H, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.pieces.cmp(&other.pieces)
+    }
+}
+
+impl<const W: usize, const H: usize, T: Piece> PartialOrd for StackPuzzle<W, H, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const W: usize, const H: usize, T: Piece + Hash> Hash for StackPuzzle<W, H, T> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.shape().hash(state);
+        self.iter().for_each(|piece| piece.hash(state));
+    }
+}
+
+impl<const W: usize, const H: usize, T: Piece> StackPuzzle<W, H, T> {
+    /// Builds a puzzle from its rows as they are. [`stack_puzzle!`](crate::stack_puzzle) checks
+    /// them at compile time.
+    pub const fn from_rows_unchecked(pieces: [[T; W]; H]) -> Self {
+        Self { pieces }
+    }
+
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized)) -> Self {
+        let mut pieces = core::array::from_fn(|_| core::array::from_fn(|_| T::zero()));
+        let mut visited = [[false; W]; H];
+        random_pieces(
+            rng,
+            pieces.as_flattened_mut(),
+            W,
+            visited.as_flattened_mut(),
+        );
+        Self { pieces }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn random() -> Self {
+        Self::random_with_rng(&mut rand::thread_rng())
+    }
+
+    pub fn cast<U: Piece + From<T>>(self) -> StackPuzzle<W, H, U> {
+        StackPuzzle {
+            pieces: self.pieces.map(|row| row.map(Into::into)),
+        }
+    }
+
+    pub fn try_cast<U: Piece>(self) -> Result<StackPuzzle<W, H, U>, ConversionError> {
+        let mut error = None;
+        let pieces = core::array::from_fn(|y| {
+            core::array::from_fn(|x| {
+                num::cast(self.pieces[y][x].clone()).unwrap_or_else(|| {
+                    error.get_or_insert(ConversionError::PieceOutOfRange { position: (x, y) });
+                    U::zero()
+                })
+            })
+        });
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(StackPuzzle { pieces }),
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn try_from_puzzle<P: Puzzle<T>>(puzzle: &P) -> Result<Self, ConversionError> {
+        let found = puzzle.shape();
+        if found != (W, H) {
+            return Err(ConversionError::ShapeMismatch {
+                expected: (W, H),
+                found,
+            });
+        }
+
+        Ok(Self {
+            pieces: core::array::from_fn(|y| core::array::from_fn(|x| puzzle[(x, y)].clone())),
+        })
+    }
+}
+
 impl<const W: usize, const H: usize, T: Piece> Puzzle<T> for StackPuzzle<W, H, T> {
+    type Iter<'a>
+        = Flatten<core::slice::Iter<'a, [T; W]>>
+    where
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.pieces.iter().flatten()
+    }
+
     fn shape(&self) -> (usize, usize) {
         (W, H)
     }
@@ -85,16 +321,6 @@ impl<const W: usize, const H: usize, T: Piece> Puzzle<T> for StackPuzzle<W, H, T
         })
     }
 
-    // type Iter = _;
-    // fn iter_indexed(&self) -> _ {
-    //     self.pieces.iter().enumerate()
-    // }
-
-    // type Iter2d = _;
-    // fn iter_indexed_2d(&self) -> Self::Iter {
-    //     self.iter_indexed().map(|(idx, p)| ((idx % W, idx / W), p))
-    // }
-
     fn slide_from(&mut self, from: (usize, usize)) -> Option<usize> {
         use core::cmp::Ordering::*;
 
@@ -174,3 +400,16 @@ impl<const W: usize, const H: usize, T: Piece> Puzzle<T> for StackPuzzle<W, H, T
         Some(distance)
     }
 }
+
+#[test]
+fn const_construction_works() {
+    const SOLVED: StackPuzzle<3, 2, u16> = StackPuzzle::<3, 2, u16>::solved();
+    const _: () = assert!(SOLVED.is_solved());
+
+    assert_eq!(SOLVED, crate::stack_puzzle![[1, 2, 3], [4, 5, 0]]);
+    assert!(Puzzle::is_solved(&StackPuzzle::<5, 1, i8>::solved()));
+
+    let scrambled: StackPuzzle<2, 2, u8> = crate::stack_puzzle![[3, 1], [0, 2]];
+    assert!(!scrambled.is_solved());
+    assert_eq!(scrambled.index_of(0), Some((0, 1)));
+}
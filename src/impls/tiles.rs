@@ -0,0 +1,282 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use rand::Rng;
+
+use core::fmt::{self, Display};
+use core::ops::Index;
+
+use crate::{is_solvable, random_pieces, BoxPuzzle, ConversionError, Position, Puzzle};
+
+/// A sliding puzzle whose tiles carry more than a number: a fragment of a picture, a colour,
+/// anything `M`.
+///
+/// Every tile keeps an identity, its number in the solved puzzle, which is what the
+/// [`Puzzle`] impl sees. Tiles are also in colour classes, and tiles of the same class are
+/// interchangeable: the puzzle is solved as soon as every cell holds a tile of the class that
+/// belongs there.
+///
+/// Only [`is_solved`](Puzzle::is_solved) and [`is_solvable`](Self::is_solvable) know about
+/// classes. Anything that reads the numbers, like [`Solver`](crate::Solver), puts every tile
+/// back where its number goes, so it may find a longer solution than needed, or none at all
+/// for a board that is solvable up to classes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TilePuzzle<M> {
+    inner: BoxPuzzle<usize>,
+    // of the tile numbered `n`, at `n - 1`
+    tiles: Box<[M]>,
+    classes: Box<[usize]>,
+}
+
+impl<M> Index<(usize, usize)> for TilePuzzle<M> {
+    type Output = usize;
+    fn index(&self, position: (usize, usize)) -> &Self::Output {
+        &self.inner[position]
+    }
+}
+
+impl<M> Index<Position> for TilePuzzle<M> {
+    type Output = usize;
+    fn index(&self, position: Position) -> &Self::Output {
+        &self.inner[position]
+    }
+}
+
+impl<M> Display for TilePuzzle<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl<M> Puzzle<usize> for TilePuzzle<M> {
+    type Iter<'a>
+        = core::slice::Iter<'a, usize>
+    where
+        M: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.inner.iter()
+    }
+
+    #[inline]
+    fn shape(&self) -> (usize, usize) {
+        self.inner.shape()
+    }
+
+    fn index_of(&self, value: usize) -> Option<(usize, usize)> {
+        self.inner.index_of(value)
+    }
+
+    /// Whether every tile is in a cell of its class, and the empty piece in the bottom-right
+    /// corner.
+    fn is_solved(&self) -> bool {
+        let len = self.tiles.len() + 1;
+        self.inner
+            .iter()
+            .enumerate()
+            .all(|(idx, &piece)| match piece {
+                0 => idx == len - 1,
+                piece => idx < len - 1 && self.classes[piece - 1] == self.classes[idx],
+            })
+    }
+
+    fn slide_from(&mut self, from: (usize, usize)) -> Option<usize> {
+        self.inner.slide_from(from)
+    }
+}
+
+impl<M> TilePuzzle<M> {
+    /// The solved puzzle with `tiles` in row-major order, each in a class of its own.
+    pub fn from_tiles(
+        (width, height): (usize, usize),
+        tiles: impl IntoIterator<Item = M>,
+    ) -> Result<Self, ConversionError> {
+        let tiles = tiles.into_iter().collect::<Box<[_]>>();
+        let classes = (0..tiles.len()).collect();
+        Self::new((width, height), tiles, classes)
+    }
+
+    /// The solved puzzle with `tiles` in row-major order, where equal tiles are
+    /// interchangeable.
+    pub fn from_classes(
+        (width, height): (usize, usize),
+        tiles: impl IntoIterator<Item = M>,
+    ) -> Result<Self, ConversionError>
+    where
+        M: PartialEq,
+    {
+        let tiles = tiles.into_iter().collect::<Box<[_]>>();
+        // the first of the equal tiles names the class
+        let classes = tiles
+            .iter()
+            .map(|tile| {
+                tiles
+                    .iter()
+                    .position(|other| other == tile)
+                    .expect("potential BUG: a tile is not equal to itself")
+            })
+            .collect();
+        Self::new((width, height), tiles, classes)
+    }
+
+    fn new(
+        (width, height): (usize, usize),
+        tiles: Box<[M]>,
+        classes: Box<[usize]>,
+    ) -> Result<Self, ConversionError> {
+        let len = width * height;
+        if len == 0 || tiles.len() != len - 1 {
+            return Err(ConversionError::InvalidPieces);
+        }
+
+        let pieces = (1..len).chain([0]).collect::<Vec<_>>();
+        Ok(Self {
+            inner: BoxPuzzle::from_pieces((width, height), pieces)?,
+            tiles,
+            classes,
+        })
+    }
+
+    /// Puts the tiles where `pieces` says, by number as in [`BoxPuzzle::from_pieces`].
+    pub fn arrange(&mut self, pieces: impl Into<Box<[usize]>>) -> Result<(), ConversionError> {
+        self.inner = BoxPuzzle::from_pieces(self.inner.shape(), pieces)?;
+        Ok(())
+    }
+
+    /// Scrambles the tiles so that the puzzle stays solvable.
+    pub fn shuffle_with_rng(&mut self, rng: &mut (impl Rng + ?Sized)) {
+        let len = self.tiles.len() + 1;
+        let mut pieces = alloc::vec![0; len];
+        random_pieces(
+            rng,
+            &mut pieces,
+            self.inner.shape().0,
+            &mut alloc::vec![false; len],
+        );
+        self.arrange(pieces)
+            .expect("potential BUG: random pieces are not a board");
+    }
+
+    #[cfg(feature = "std")]
+    pub fn shuffle(&mut self) {
+        self.shuffle_with_rng(&mut rand::thread_rng());
+    }
+
+    /// The tile at `position`, or `None` for the empty piece and outside the board.
+    pub fn tile(&self, position: impl Into<Position>) -> Option<&M> {
+        match self.get(position)? {
+            0 => None,
+            &piece => Some(&self.tiles[piece - 1]),
+        }
+    }
+
+    /// The class of the tile at `position`, like [`tile`](Self::tile).
+    pub fn class(&self, position: impl Into<Position>) -> Option<usize> {
+        match self.get(position)? {
+            0 => None,
+            &piece => Some(self.classes[piece - 1]),
+        }
+    }
+
+    /// Whether the puzzle can be solved at all.
+    ///
+    /// Swapping two tiles of the same class changes nothing but the parity of the tiles, so a
+    /// class of two or more makes every board solvable. Rows and columns of one keep the order
+    /// of their tiles, which then has to match class by class.
+    pub fn is_solvable(&self) -> bool {
+        let (width, height) = self.shape();
+        if width == 1 || height == 1 {
+            let tiles = self.inner.iter().filter(|&&piece| piece != 0);
+            return tiles
+                .zip(self.classes.iter())
+                .all(|(&piece, &class)| self.classes[piece - 1] == class);
+        }
+
+        let shared = self
+            .classes
+            .iter()
+            .enumerate()
+            .any(|(idx, &class)| class != idx);
+        shared || is_solvable(&self.inner.iter().copied().collect::<Vec<_>>(), width)
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn tiles_work() {
+    use crate::Direction::*;
+    use itertools::Itertools;
+    use std::collections::{HashSet, VecDeque};
+
+    let mut puzzle =
+        TilePuzzle::from_tiles((2, 2), ["top left", "top right", "bottom left"]).unwrap();
+    assert!(puzzle.is_solved());
+    assert_eq!(puzzle.tile((1, 0)), Some(&"top right"));
+    assert_eq!(puzzle.slide_towards(Right, 1), Some(1));
+    assert_eq!(puzzle.tile((1, 1)), Some(&"bottom left"));
+    assert_eq!(puzzle.tile((0, 1)), None);
+    assert!(!puzzle.is_solved());
+    assert!(TilePuzzle::from_tiles((2, 2), ["too", "few"]).is_err());
+
+    // 1 2 3      r r g
+    // 4 5   ->   g b
+    let colours = ['r', 'r', 'g', 'g', 'b'];
+    let mut puzzle = TilePuzzle::from_classes((3, 2), colours).unwrap();
+    puzzle.arrange(vec![2, 1, 4, 3, 5, 0]).unwrap();
+    assert!(puzzle.is_solved());
+    assert_eq!(puzzle.class((2, 0)), Some(2));
+    puzzle.arrange(vec![2, 1, 3, 4, 5, 0]).unwrap();
+    assert!(puzzle.is_solved());
+    // two equal tiles swapped, which the solver cannot undo tile by tile
+    assert!(puzzle.is_solvable());
+    assert!(!crate::Solver::new(&puzzle).is_solvable());
+    assert_eq!(crate::Solver::new(&puzzle).solve(), None);
+    puzzle.arrange(vec![1, 2, 5, 4, 3, 0]).unwrap();
+    assert!(!puzzle.is_solved());
+
+    // against what reaches a solved board, on every arrangement of a few small boards
+    let boards = [
+        ((3, 2), vec!['r', 'r', 'g', 'g', 'b']),
+        ((3, 2), vec!['r', 'g', 'b', 'c', 'm']),
+        ((4, 1), vec!['r', 'g', 'r']),
+        ((1, 4), vec!['r', 'r', 'g']),
+    ];
+    for (shape, colours) in boards {
+        let len = shape.0 * shape.1;
+        let puzzle = TilePuzzle::from_classes(shape, colours).unwrap();
+
+        let arrangements = (0..len).permutations(len).collect::<Vec<_>>();
+
+        let arranged = |pieces: &Vec<usize>| {
+            let mut puzzle = puzzle.clone();
+            puzzle.arrange(pieces.clone()).unwrap();
+            puzzle
+        };
+        let mut reachable = arrangements
+            .iter()
+            .map(arranged)
+            .filter(|puzzle| puzzle.is_solved())
+            .collect::<HashSet<_>>();
+        let mut queue = reachable.iter().cloned().collect::<VecDeque<_>>();
+        while let Some(puzzle) = queue.pop_front() {
+            for direction in crate::Direction::ALL {
+                let mut next = puzzle.clone();
+                if next.slide_towards(direction, 1) == Some(1) && reachable.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        for pieces in &arrangements {
+            let puzzle = arranged(pieces);
+            assert_eq!(
+                puzzle.is_solvable(),
+                reachable.contains(&puzzle),
+                "{pieces:?}"
+            );
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut shuffled = puzzle.clone();
+        shuffled.shuffle_with_rng(&mut rng);
+        assert!(reachable.contains(&shuffled));
+    }
+}
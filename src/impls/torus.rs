@@ -0,0 +1,306 @@
+use rand::{seq::SliceRandom, Rng};
+
+use core::fmt::{self, Display};
+use core::iter::once;
+use core::ops::Index;
+
+use crate::{is_solvable, ConversionError, Direction, LegalMoves, Piece, Position, Puzzle};
+
+/// A sliding puzzle on a torus: rows and columns wrap around, so tiles slid off one edge come
+/// back on the other.
+///
+/// [`slide_towards`](Puzzle::slide_towards) wraps as needed, and
+/// [`slide_from`](Puzzle::slide_from) goes the shorter way round, moving tiles right or down
+/// when both ways are as long.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TorusPuzzle<T: Piece> {
+    inner: Box<[T]>,
+    width: usize,
+}
+
+impl Default for TorusPuzzle<u8> {
+    fn default() -> Self {
+        Self::solved((4, 4))
+    }
+}
+
+impl<T: Piece + Display> Display for TorusPuzzle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = ((self.inner.len() - 1) as f32).log10() as usize + 1;
+        for row in self.inner.chunks(self.width) {
+            for piece in row {
+                if piece.is_zero() {
+                    write!(f, "{: >digits$} ", "")?;
+                } else {
+                    write!(f, "{: >digits$} ", piece)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Piece> Index<(usize, usize)> for TorusPuzzle<T> {
+    type Output = T;
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.inner[y * self.width + x]
+    }
+}
+
+impl<T: Piece> Index<Position> for TorusPuzzle<T> {
+    type Output = T;
+    fn index(&self, Position { x, y }: Position) -> &Self::Output {
+        &self[(x, y)]
+    }
+}
+
+impl<T: Piece> Puzzle<T> for TorusPuzzle<T> {
+    type Iter<'a>
+        = core::slice::Iter<'a, T>
+    where
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.inner.iter()
+    }
+
+    #[inline]
+    fn shape(&self) -> (usize, usize) {
+        (self.width, self.inner.len() / self.width)
+    }
+
+    fn index_of(&self, value: T) -> Option<(usize, usize)> {
+        self.inner
+            .iter()
+            .position(|x| *x == value)
+            .map(|idx| (idx % self.width, idx / self.width))
+    }
+
+    fn slide_from(&mut self, from: (usize, usize)) -> Option<usize> {
+        let (width, height) = self.shape();
+        if !matches!(from, (x, y) if x < width && y < height) {
+            return None;
+        }
+
+        let empty = self
+            .index_of(num::zero())
+            .expect("potential BUG: could not find an empty piece");
+
+        // how far the tiles go if they move right or down
+        let (forward, length, directions) = match (from.0 == empty.0, from.1 == empty.1) {
+            (true, true) => return Some(0),
+            (false, false) => return None,
+            (false, true) => (
+                (empty.0 + width - from.0) % width,
+                width,
+                (Direction::Right, Direction::Left),
+            ),
+            (true, false) => (
+                (empty.1 + height - from.1) % height,
+                height,
+                (Direction::Down, Direction::Up),
+            ),
+        };
+
+        match forward <= length - forward {
+            true => self.slide_towards(directions.0, forward),
+            false => self.slide_towards(directions.1, length - forward),
+        }
+    }
+
+    /// Slides the `distance` tiles behind the empty piece, wrapping around the edges. The
+    /// distance has to be shorter than the row or column.
+    fn slide_towards(&mut self, direction: Direction, distance: usize) -> Option<usize> {
+        let (width, height) = self.shape();
+        let length = match direction {
+            Direction::Left | Direction::Right => width,
+            Direction::Up | Direction::Down => height,
+        };
+        if distance >= length {
+            return None;
+        }
+
+        let (dx, dy): (isize, isize) = direction.into();
+        let mut empty = self
+            .index_of(num::zero())
+            .expect("potential BUG: could not find an empty piece");
+        for _ in 0..distance {
+            let from = (
+                (empty.0 as isize - dx).rem_euclid(width as isize) as usize,
+                (empty.1 as isize - dy).rem_euclid(height as isize) as usize,
+            );
+            self.inner
+                .swap(empty.1 * width + empty.0, from.1 * width + from.0);
+            empty = from;
+        }
+
+        Some(distance)
+    }
+
+    /// Every count short of the whole row or column, both ways round.
+    fn legal_moves(&self) -> LegalMoves {
+        let (width, height) = self.shape();
+        LegalMoves::new([height - 1, height - 1, width - 1, width - 1])
+    }
+}
+
+impl<T: Piece> TorusPuzzle<T> {
+    pub fn solved((width, height): (usize, usize)) -> Self {
+        let pieces = (1..width * height)
+            .chain(once(0))
+            .map(num::cast)
+            .collect::<Option<_>>()
+            .expect("could not cast pieces to usize");
+
+        Self {
+            inner: pieces,
+            width,
+        }
+    }
+
+    /// Builds a puzzle from its pieces, row by row, which must be `0..width * height` in any
+    /// order.
+    pub fn from_pieces(
+        (width, height): (usize, usize),
+        pieces: impl Into<Box<[T]>>,
+    ) -> Result<Self, ConversionError> {
+        let inner = pieces.into();
+        let len = width * height;
+        if width == 0 || inner.len() != len {
+            return Err(ConversionError::InvalidPieces);
+        }
+
+        let mut seen = vec![false; len];
+        for piece in inner.iter() {
+            match num::cast::<_, usize>(piece.clone()) {
+                Some(piece) if piece < len && !seen[piece] => seen[piece] = true,
+                _ => return Err(ConversionError::InvalidPieces),
+            }
+        }
+
+        Ok(Self { inner, width })
+    }
+
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized), (width, height): (usize, usize)) -> Self {
+        let mut puzzle = Self::solved((width, height));
+
+        if is_cycle((width, height)) {
+            // only rotations are reachable; walking the empty piece around gets to any of them
+            for _ in 0..(width * height).pow(2).max(100) {
+                let direction = Direction::ALL.choose(rng).copied();
+                puzzle.slide_towards(direction.expect("potential BUG: no directions"), 1);
+            }
+            return puzzle;
+        }
+
+        puzzle.inner.shuffle(rng);
+        if !puzzle.is_solvable() {
+            let mut tiles = puzzle.inner.iter_mut().filter(|piece| !piece.is_zero());
+            let (Some(a), Some(b)) = (tiles.next(), tiles.next()) else {
+                unreachable!("potential BUG: a board with fewer than two tiles is a cycle");
+            };
+            core::mem::swap(a, b);
+        }
+
+        puzzle
+    }
+
+    pub fn random((width, height): (usize, usize)) -> Self {
+        Self::random_with_rng(&mut rand::thread_rng(), (width, height))
+    }
+
+    /// Whether the puzzle can be solved at all.
+    ///
+    /// If the width or the height is odd, the empty piece can go round an odd cycle, which
+    /// takes parity out of the picture and makes everything solvable. Otherwise parity decides
+    /// as on a plain board. Single rows and columns, and 2x2, are rings on which tiles keep
+    /// their order.
+    pub fn is_solvable(&self) -> bool {
+        let (width, height) = self.shape();
+        if is_cycle((width, height)) {
+            let ring = match (width, height) {
+                (2, 2) => vec![0, 1, 3, 2],
+                _ => (0..width * height).collect(),
+            };
+            let tiles = |pieces: Vec<usize>| {
+                pieces
+                    .into_iter()
+                    .filter(|&piece| piece != 0)
+                    .collect::<Vec<_>>()
+            };
+            let current = tiles(
+                ring.iter()
+                    .map(|&idx| num::cast(self.inner[idx].clone()))
+                    .collect::<Option<_>>()
+                    .expect("could not cast pieces to usize"),
+            );
+            let solved = tiles(
+                ring.iter()
+                    .map(|&idx| (idx + 1) % (width * height))
+                    .collect(),
+            );
+
+            return (0..solved.len().max(1)).any(|shift| {
+                current
+                    .iter()
+                    .zip(solved.iter().cycle().skip(shift))
+                    .all(|(a, b)| a == b)
+            });
+        }
+
+        match (width % 2, height % 2) {
+            (0, 0) => is_solvable(&self.inner, width),
+            _ => true,
+        }
+    }
+}
+
+// boards whose torus is a single ring
+fn is_cycle((width, height): (usize, usize)) -> bool {
+    width == 1 || height == 1 || (width, height) == (2, 2)
+}
+
+#[test]
+fn torus_works() {
+    use crate::{Direction::*, Solver};
+
+    let mut puzzle = TorusPuzzle::default();
+    assert_eq!(puzzle.slide_towards(Left, 1), Some(1));
+    assert_eq!(puzzle.index_of(0), Some((0, 3)));
+    assert_eq!(puzzle[(3, 3)], 13);
+    assert_eq!(puzzle.slide_towards(Up, 2), Some(2));
+    assert_eq!(puzzle.index_of(0), Some((0, 1)));
+    assert_eq!(puzzle.slide_towards(Up, 4), None);
+
+    // the shorter way round, and rightwards on a tie
+    assert_eq!(puzzle.slide_from((3, 1)), Some(1));
+    assert_eq!(puzzle.index_of(0), Some((3, 1)));
+    assert_eq!(puzzle.slide_from((1, 1)), Some(2));
+    assert_eq!(puzzle.index_of(0), Some((1, 1)));
+    assert_eq!(puzzle.slide_from((0, 0)), None);
+    assert!(puzzle.is_solvable());
+
+    let solution = Solver::new_torus(&puzzle).solve().unwrap();
+    assert!(solution.len() <= 6);
+    for direction in solution {
+        assert_eq!(puzzle.slide_towards(direction, 1), Some(1));
+    }
+    assert_eq!(puzzle, TorusPuzzle::default());
+
+    // odd sides make any permutation solvable, even ones keep the usual parity
+    let swapped = TorusPuzzle::<u8>::from_pieces((3, 3), vec![2, 1, 3, 4, 5, 6, 7, 8, 0]).unwrap();
+    assert!(swapped.is_solvable());
+    assert_eq!(Solver::new_torus(&swapped).optimal_length(), Some(5));
+    let swapped = TorusPuzzle::<u8>::from_pieces((2, 4), vec![2, 1, 3, 4, 5, 6, 7, 0]).unwrap();
+    assert!(!swapped.is_solvable());
+    assert_eq!(Solver::new_torus(&swapped).solve(), None);
+    let rotated = TorusPuzzle::<u8>::from_pieces((5, 1), vec![3, 0, 4, 1, 2]).unwrap();
+    assert!(rotated.is_solvable());
+    let swapped = TorusPuzzle::<u8>::from_pieces((2, 2), vec![2, 1, 3, 0]).unwrap();
+    assert!(!swapped.is_solvable());
+
+    let mut rng = rand::thread_rng();
+    for shape in [(4, 4), (3, 5), (1, 4), (2, 2)] {
+        assert!(TorusPuzzle::<u8>::random_with_rng(&mut rng, shape).is_solvable());
+    }
+}
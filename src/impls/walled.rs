@@ -0,0 +1,657 @@
+use rand::{seq::SliceRandom, Rng};
+
+use core::fmt::{self, Display};
+use core::ops::Index;
+use std::collections::{HashSet, VecDeque};
+
+use crate::{ConversionError, LegalMoves, Piece, Position, Puzzle};
+
+// regions up to this many cells that no rule covers are searched exhaustively
+const SEARCH_LIMIT: usize = 8;
+
+/// A sliding puzzle with walls: cells that are blocked for good and that nothing slides past.
+///
+/// Tiles belong in order on the open cells, row by row from the top left, with the empty piece
+/// on the last one. Walls read as `T::zero()`, like the empty piece; tell them apart with
+/// [`is_wall`](Self::is_wall).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WalledPuzzle<T: Piece> {
+    inner: Box<[T]>,
+    walls: Box<[bool]>,
+    width: usize,
+}
+
+/// How the cells the empty piece can reach are connected, which decides what can be solved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    /// Any cell could be removed without splitting the rest: solvable exactly when the parity
+    /// of the permutation matches that of where the empty piece is, as on a plain board.
+    TwoConnected,
+    /// A ring: tiles can only go round, keeping their order.
+    Cycle,
+    /// A single line, or the empty piece on its own: tiles can never pass each other.
+    Path,
+    /// Anything else, with bottlenecks that tiles may or may not get through.
+    Branching,
+}
+
+/// What [`WalledPuzzle::reachability`] found out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reachability {
+    /// Open cells the empty piece can reach, row by row.
+    pub reachable: Vec<(usize, usize)>,
+    /// Tiles the empty piece can never reach, which therefore never move.
+    pub stuck: Vec<(usize, usize)>,
+    pub region: Region,
+    /// `None` if the region is [`Region::Branching`] and too large to search.
+    pub solvable: Option<bool>,
+}
+
+impl<T: Piece + Display> Display for WalledPuzzle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = ((self.inner.len() - 1) as f32).log10() as usize + 1;
+        for (row, walls) in self
+            .inner
+            .chunks(self.width)
+            .zip(self.walls.chunks(self.width))
+        {
+            for (piece, &wall) in row.iter().zip(walls) {
+                if wall {
+                    write!(f, "{: >digits$} ", "#")?;
+                } else if piece.is_zero() {
+                    write!(f, "{: >digits$} ", "")?;
+                } else {
+                    write!(f, "{: >digits$} ", piece)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Piece> Index<(usize, usize)> for WalledPuzzle<T> {
+    type Output = T;
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.inner[y * self.width + x]
+    }
+}
+
+impl<T: Piece> Index<Position> for WalledPuzzle<T> {
+    type Output = T;
+    fn index(&self, Position { x, y }: Position) -> &Self::Output {
+        &self[(x, y)]
+    }
+}
+
+impl<T: Piece> Puzzle<T> for WalledPuzzle<T> {
+    type Iter<'a>
+        = core::slice::Iter<'a, T>
+    where
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.inner.iter()
+    }
+
+    #[inline]
+    fn shape(&self) -> (usize, usize) {
+        (self.width, self.inner.len() / self.width)
+    }
+
+    /// Finds `value` on an open cell; walls are never found.
+    fn index_of(&self, value: T) -> Option<(usize, usize)> {
+        self.inner
+            .iter()
+            .zip(self.walls.iter())
+            .position(|(x, &wall)| !wall && *x == value)
+            .map(|idx| (idx % self.width, idx / self.width))
+    }
+
+    fn is_solved(&self) -> bool {
+        let open = self.open().count();
+        self.open()
+            .enumerate()
+            .all(|(idx, position)| num::cast(self[position].clone()) == Some(goal_piece(idx, open)))
+    }
+
+    /// Slides like on a plain board, except that no tile moves past a wall.
+    fn slide_from(&mut self, from: (usize, usize)) -> Option<usize> {
+        let (width, height) = self.shape();
+        if !matches!(from, (x, y) if x < width && y < height) || self.is_wall(from) {
+            return None;
+        }
+
+        let empty = self
+            .index_of(num::zero())
+            .expect("potential BUG: could not find an empty piece");
+        if from == empty {
+            return Some(0);
+        }
+        if from.0 != empty.0 && from.1 != empty.1 {
+            return None;
+        }
+
+        let distance = from.0.abs_diff(empty.0) + from.1.abs_diff(empty.1);
+        let step = (
+            (from.0 as isize - empty.0 as isize).signum(),
+            (from.1 as isize - empty.1 as isize).signum(),
+        );
+        // from the empty piece to `from`
+        let line = (0..=distance as isize)
+            .map(|n| {
+                let x = empty.0.wrapping_add_signed(step.0 * n);
+                let y = empty.1.wrapping_add_signed(step.1 * n);
+                y * width + x
+            })
+            .collect::<Vec<_>>();
+
+        if line.iter().any(|&idx| self.walls[idx]) {
+            return None;
+        }
+        for pair in line.windows(2) {
+            self.inner.swap(pair[0], pair[1]);
+        }
+
+        Some(distance)
+    }
+
+    /// Slides stop at walls as well as at the edges.
+    fn legal_moves(&self) -> LegalMoves {
+        let empty = self
+            .index_of(num::zero())
+            .expect("potential BUG: could not find an empty piece");
+        LegalMoves::until(self.shape(), empty, |position| self.is_wall(position))
+    }
+}
+
+impl<T: Piece> WalledPuzzle<T> {
+    /// The solved puzzle with walls at `walls`, which must leave at least one open cell.
+    pub fn solved(
+        (width, height): (usize, usize),
+        walls: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Self {
+        let mut mask = vec![false; width * height].into_boxed_slice();
+        for (x, y) in walls {
+            assert!(
+                x < width && y < height,
+                "wall ({x}, {y}) is out of bounds for a {width}x{height} board"
+            );
+            mask[y * width + x] = true;
+        }
+        assert!(mask.contains(&false), "a board needs an open cell");
+
+        let mut puzzle = Self {
+            inner: vec![T::zero(); width * height].into_boxed_slice(),
+            walls: mask,
+            width,
+        };
+        let goals = puzzle.goals();
+        for (piece, goal) in puzzle.inner.iter_mut().zip(goals) {
+            *piece = num::cast(goal).expect("could not cast pieces to usize");
+        }
+        puzzle
+    }
+
+    /// Builds a puzzle from its pieces and walls, row by row. Open cells must hold
+    /// `0..open cells` in any order, and walls `T::zero()`.
+    pub fn from_pieces(
+        (width, height): (usize, usize),
+        pieces: impl Into<Box<[T]>>,
+        walls: impl Into<Box<[bool]>>,
+    ) -> Result<Self, ConversionError> {
+        let (inner, walls) = (pieces.into(), walls.into());
+        let len = width * height;
+        if width == 0 || inner.len() != len || walls.len() != len {
+            return Err(ConversionError::InvalidPieces);
+        }
+
+        let open = walls.iter().filter(|&&wall| !wall).count();
+        let mut seen = vec![false; open];
+        for (piece, &wall) in inner.iter().zip(walls.iter()) {
+            match (num::cast::<_, usize>(piece.clone()), wall) {
+                (Some(0), true) => {}
+                (Some(piece), false) if piece < open && !seen[piece] => seen[piece] = true,
+                _ => return Err(ConversionError::InvalidPieces),
+            }
+        }
+
+        Ok(Self {
+            inner,
+            walls,
+            width,
+        })
+    }
+
+    /// Scrambles the solved puzzle with walls at `walls` by moving the empty piece at random,
+    /// so that it is always solvable.
+    pub fn random_with_rng(
+        rng: &mut (impl Rng + ?Sized),
+        (width, height): (usize, usize),
+        walls: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Self {
+        let mut puzzle = Self::solved((width, height), walls);
+        let open = puzzle.open().count();
+
+        let mut previous = None;
+        for _ in 0..(open * open).max(100) {
+            let empty = puzzle
+                .index_of(num::zero())
+                .expect("potential BUG: could not find an empty piece");
+            let mut neighbors = puzzle.neighbors(empty).collect::<Vec<_>>();
+            // going straight back is a wasted move, unless there is nowhere else to go
+            if neighbors.len() > 1 {
+                neighbors.retain(|&neighbor| Some(neighbor) != previous);
+            }
+
+            let Some(&next) = neighbors.choose(rng) else {
+                break;
+            };
+            puzzle.slide_from(next);
+            previous = Some(empty);
+        }
+
+        puzzle
+    }
+
+    pub fn random(
+        (width, height): (usize, usize),
+        walls: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Self {
+        Self::random_with_rng(&mut rand::thread_rng(), (width, height), walls)
+    }
+
+    /// Like [`random_with_rng`](Self::random_with_rng), with `walls` of them placed at random
+    /// where they do not cut the board in two.
+    pub fn random_level_with_rng(
+        rng: &mut (impl Rng + ?Sized),
+        (width, height): (usize, usize),
+        walls: usize,
+    ) -> Self {
+        let len = width * height;
+        assert!(
+            walls + 2 <= len,
+            "{walls} walls leave too little room on a {width}x{height} board"
+        );
+
+        let mut cells = (0..len)
+            .map(|idx| (idx % width, idx / width))
+            .collect::<Vec<_>>();
+        cells.shuffle(rng);
+
+        let mut level = Self::solved((width, height), []);
+        for cell in cells {
+            if level.walls().count() == walls {
+                break;
+            }
+
+            let mut walled = level.clone();
+            walled.walls[cell.1 * width + cell.0] = true;
+            let open = walled.open().collect::<Vec<_>>();
+            if walled.component(open[0]).len() == open.len() {
+                level = walled;
+            }
+        }
+
+        let walls = level.walls().collect::<Vec<_>>();
+        Self::random_with_rng(rng, (width, height), walls)
+    }
+
+    pub fn random_level((width, height): (usize, usize), walls: usize) -> Self {
+        Self::random_level_with_rng(&mut rand::thread_rng(), (width, height), walls)
+    }
+
+    pub fn is_wall(&self, (x, y): (usize, usize)) -> bool {
+        self.walls[y * self.width + x]
+    }
+
+    /// Where the walls are, in row-major order.
+    pub fn walls(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width;
+        (0..self.walls.len())
+            .filter(|&idx| self.walls[idx])
+            .map(move |idx| (idx % width, idx / width))
+    }
+
+    /// Whether the puzzle can be solved, if [`reachability`](Self::reachability) can tell.
+    pub fn is_solvable(&self) -> Option<bool> {
+        self.reachability().solvable
+    }
+
+    /// Works out where the empty piece can go, and from that whether the puzzle is solvable.
+    pub fn reachability(&self) -> Reachability {
+        let empty = self
+            .index_of(num::zero())
+            .expect("potential BUG: could not find an empty piece");
+
+        let mut reachable = self.component(empty);
+        reachable.sort_by_key(|&(x, y)| (y, x));
+        let stuck = self
+            .open()
+            .filter(|position| !reachable.contains(position))
+            .collect::<Vec<_>>();
+
+        let edges = reachable
+            .iter()
+            .map(|&position| self.neighbors(position).count())
+            .sum::<usize>()
+            / 2;
+        let max_degree = reachable
+            .iter()
+            .map(|&position| self.neighbors(position).count())
+            .max()
+            .unwrap_or(0);
+        let region = match (edges + 1 == reachable.len(), max_degree) {
+            (true, 0..=2) => Region::Path,
+            (false, 2) => Region::Cycle,
+            _ if self.is_two_connected(&reachable) => Region::TwoConnected,
+            _ => Region::Branching,
+        };
+
+        let goals = self.goals();
+        let in_place = |&(x, y): &(usize, usize)| {
+            num::cast(self[(x, y)].clone()) == Some(goals[y * self.width + x])
+        };
+        let solvable = match stuck.iter().all(in_place) {
+            false => Some(false),
+            true => self.solvable_within(&reachable, region, &goals),
+        };
+
+        Reachability {
+            reachable,
+            stuck,
+            region,
+            solvable,
+        }
+    }
+
+    // assuming everything outside `region` is in place already, `goals` being from `goals()`
+    fn solvable_within(
+        &self,
+        region: &[(usize, usize)],
+        kind: Region,
+        goals: &[usize],
+    ) -> Option<bool> {
+        let value = |position: (usize, usize)| -> usize {
+            num::cast(self[position].clone()).expect("could not cast piece to usize")
+        };
+        let goal = |(x, y): (usize, usize)| goals[y * self.width + x];
+
+        match kind {
+            Region::TwoConnected => {
+                // pieces are where pieces belong, so this is a permutation of `region`
+                let index_of = |piece: usize| {
+                    region
+                        .iter()
+                        .position(|&position| goal(position) == piece)
+                        .expect("potential BUG: a piece belongs outside its region")
+                };
+                let targets = region
+                    .iter()
+                    .map(|&position| index_of(value(position)))
+                    .collect::<Vec<_>>();
+
+                let parity = permutation_parity(&targets);
+
+                let empty = self
+                    .index_of(num::zero())
+                    .expect("potential BUG: could not find an empty piece");
+                let home = region[index_of(0)];
+                let distance = empty.0.abs_diff(home.0) + empty.1.abs_diff(home.1);
+
+                Some(parity == distance % 2)
+            }
+            Region::Cycle | Region::Path => {
+                let line = self.walk(region, kind);
+                let tiles = |pieces: Vec<usize>| {
+                    pieces
+                        .into_iter()
+                        .filter(|&piece| piece != 0)
+                        .collect::<Vec<_>>()
+                };
+                let current = tiles(line.iter().map(|&position| value(position)).collect());
+                let solved = tiles(line.iter().map(|&position| goal(position)).collect());
+
+                Some(match kind {
+                    Region::Cycle => (0..solved.len().max(1)).any(|shift| {
+                        current
+                            .iter()
+                            .zip(solved.iter().cycle().skip(shift))
+                            .all(|(a, b)| a == b)
+                    }),
+                    _ => current == solved,
+                })
+            }
+            Region::Branching if region.len() <= SEARCH_LIMIT => Some(self.search(region)),
+            Region::Branching => None,
+        }
+    }
+
+    // whether the board can be solved, by trying every reachable arrangement of `region`
+    fn search(&self, region: &[(usize, usize)]) -> bool {
+        let key = |puzzle: &Self| {
+            region
+                .iter()
+                .map(|&position| num::cast::<_, usize>(puzzle[position].clone()))
+                .collect::<Option<Vec<_>>>()
+                .expect("could not cast pieces to usize")
+        };
+
+        let mut seen = HashSet::from([key(self)]);
+        let mut queue = VecDeque::from([self.clone()]);
+        while let Some(puzzle) = queue.pop_front() {
+            if puzzle.is_solved() {
+                return true;
+            }
+
+            let empty = puzzle
+                .index_of(num::zero())
+                .expect("potential BUG: could not find an empty piece");
+            for neighbor in puzzle.neighbors(empty) {
+                let mut next = puzzle.clone();
+                next.slide_from(neighbor);
+                if seen.insert(key(&next)) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        false
+    }
+
+    // the cells of a path from one end, or of a cycle from anywhere, in order
+    fn walk(&self, region: &[(usize, usize)], kind: Region) -> Vec<(usize, usize)> {
+        let start = match kind {
+            Region::Path => region
+                .iter()
+                .copied()
+                .find(|&position| self.neighbors(position).count() <= 1),
+            _ => region.first().copied(),
+        }
+        .expect("potential BUG: a path or cycle without a start");
+
+        let mut line = vec![start];
+        let mut previous = None;
+        let mut current = start;
+        while let Some(next) = self
+            .neighbors(current)
+            .find(|&next| Some(next) != previous && next != start)
+        {
+            line.push(next);
+            previous = Some(current);
+            current = next;
+        }
+        line
+    }
+
+    // open cells, row by row
+    fn open(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width;
+        (0..self.walls.len())
+            .filter(|&idx| !self.walls[idx])
+            .map(move |idx| (idx % width, idx / width))
+    }
+
+    // the piece that belongs on every cell, row by row, walls getting `0` like the empty piece
+    fn goals(&self) -> Vec<usize> {
+        let open = self.open().count();
+        let mut goals = vec![0; self.walls.len()];
+        for (idx, (x, y)) in self.open().enumerate() {
+            goals[y * self.width + x] = goal_piece(idx, open);
+        }
+        goals
+    }
+
+    fn neighbors(&self, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (width, height) = self.shape();
+        [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| {
+                let next = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+                (next.0 < width && next.1 < height && !self.is_wall(next)).then_some(next)
+            })
+    }
+
+    // open cells connected to `start`
+    fn component(&self, start: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(position) = stack.pop() {
+            for neighbor in self.neighbors(position) {
+                if seen.insert(neighbor) {
+                    stack.push(neighbor);
+                }
+            }
+        }
+        seen.into_iter().collect()
+    }
+
+    // whether removing any one cell of `region` leaves the rest connected
+    fn is_two_connected(&self, region: &[(usize, usize)]) -> bool {
+        region.len() >= 3
+            && region.iter().all(|&removed| {
+                let start = *region
+                    .iter()
+                    .find(|&&position| position != removed)
+                    .expect("potential BUG: a region of one cell");
+
+                let mut seen = HashSet::from([start, removed]);
+                let mut stack = vec![start];
+                while let Some(position) = stack.pop() {
+                    for neighbor in self.neighbors(position) {
+                        if seen.insert(neighbor) {
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+                seen.len() == region.len()
+            })
+    }
+}
+
+// the piece that belongs on the `idx`-th of `open` open cells
+fn goal_piece(idx: usize, open: usize) -> usize {
+    match idx + 1 {
+        piece if piece == open => 0,
+        piece => piece,
+    }
+}
+
+// 0 for even permutations, 1 for odd ones
+fn permutation_parity(targets: &[usize]) -> usize {
+    let mut seen = vec![false; targets.len()];
+    let mut parity = 0;
+    for start in 0..targets.len() {
+        let mut length = 0;
+        let mut idx = start;
+        while !seen[idx] {
+            seen[idx] = true;
+            idx = targets[idx];
+            length += 1;
+        }
+        // a cycle of n is n - 1 transpositions
+        parity += length.max(1) - 1;
+    }
+    parity % 2
+}
+
+#[test]
+fn walled_works() {
+    use crate::Direction::*;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    // 1 2 3
+    // 4 # 5
+    // 6 7
+    let mut ring = WalledPuzzle::<u8>::solved((3, 3), [(1, 1)]);
+    assert!(ring.is_solved());
+    assert_eq!(ring.to_string(), "1 2 3 \n4 # 5 \n6 7   \n");
+    assert_eq!(ring.index_of(0), Some((2, 2)));
+    assert_eq!(ring.slide_from((2, 0)), Some(2));
+    assert_eq!(ring.slide_towards(Right, 1), Some(1));
+    assert_eq!(ring.slide_towards(Up, 1), None);
+    assert_eq!(ring.slide_from((1, 1)), None);
+    let reachability = ring.reachability();
+    assert_eq!(reachability.region, Region::Cycle);
+    assert_eq!(reachability.reachable.len(), 8);
+    assert_eq!(reachability.solvable, Some(true));
+
+    // going round the ring keeps the order of the tiles, which the swap breaks
+    let swapped = WalledPuzzle::<u8>::from_pieces((3, 3), vec![2, 1, 3, 4, 0, 5, 6, 7, 0], {
+        let mut walls = [false; 9];
+        walls[4] = true;
+        walls
+    })
+    .unwrap();
+    assert_eq!(swapped.is_solvable(), Some(false));
+
+    // a wall down the middle leaves the left column out of reach
+    //   1 # 2
+    //   3 # 4
+    //   5 #
+    let split = WalledPuzzle::<u8>::solved((3, 3), [(1, 0), (1, 1), (1, 2)]);
+    let mut scrambled = split.clone();
+    scrambled.slide_from((2, 0));
+    let reachability = scrambled.reachability();
+    assert_eq!(reachability.region, Region::Path);
+    assert_eq!(reachability.stuck, [(0, 0), (0, 1), (0, 2)]);
+    assert_eq!(reachability.solvable, Some(true));
+    let mut pieces = split.iter().copied().collect::<Vec<_>>();
+    pieces.swap(0, 3);
+    let walls = (0..9).map(|idx| idx % 3 == 1).collect::<Vec<_>>();
+    let broken = WalledPuzzle::from_pieces((3, 3), pieces, walls).unwrap();
+    assert_eq!(broken.reachability().stuck.len(), 3);
+    assert_eq!(broken.is_solvable(), Some(false));
+
+    // with room to spare, parity decides as usual
+    let mut open = WalledPuzzle::<u8>::solved((4, 4), [(0, 0)]);
+    assert_eq!(open.reachability().region, Region::TwoConnected);
+    open.slide_from((3, 0));
+    open.slide_from((1, 0));
+    assert_eq!(open.is_solvable(), Some(true));
+    open.inner.swap(5, 6);
+    assert_eq!(open.is_solvable(), Some(false));
+
+    // a T: branching, so searched
+    // # 1 #
+    // 2 3 4
+    // # 5
+    let tee = WalledPuzzle::<u8>::solved((3, 3), [(0, 0), (2, 0), (0, 2)]);
+    assert_eq!(tee.reachability().region, Region::Branching);
+    assert_eq!(tee.is_solvable(), Some(true));
+    let mut swapped = tee.clone();
+    swapped.inner.swap(1, 3);
+    assert_eq!(swapped.is_solvable(), Some(false));
+
+    // scrambled by sliding, so solvable whenever that can be told
+    let mut rng = Xoshiro256StarStar::seed_from_u64(0x6a75676f);
+    for ((width, height), walls) in [((4, 4), 1), ((4, 4), 3), ((4, 4), 6), ((3, 3), 2)] {
+        let level = WalledPuzzle::<u8>::random_level_with_rng(&mut rng, (width, height), walls);
+        assert_eq!(level.walls().count(), walls);
+        let reachability = level.reachability();
+        assert_eq!(reachability.reachable.len(), width * height - walls);
+        let decidable = reachability.region != Region::Branching
+            || reachability.reachable.len() <= SEARCH_LIMIT;
+        assert_eq!(reachability.solvable, decidable.then_some(true), "{level}");
+    }
+}
@@ -0,0 +1,120 @@
+use core::iter::{Enumerate, FusedIterator};
+
+use crate::Direction;
+
+/// Iterator over `((x, y), piece)` in row-major order, returned by
+/// [`Puzzle::iter_indexed`](crate::Puzzle::iter_indexed).
+#[derive(Debug, Clone)]
+pub struct IterIndexed<I> {
+    inner: Enumerate<I>,
+    width: usize,
+}
+
+impl<I: Iterator> IterIndexed<I> {
+    pub(crate) fn new(inner: I, width: usize) -> Self {
+        Self {
+            inner: inner.enumerate(),
+            width,
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for IterIndexed<I> {
+    type Item = ((usize, usize), I::Item);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(idx, piece)| ((idx % self.width, idx / self.width), piece))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I: ExactSizeIterator> ExactSizeIterator for IterIndexed<I> {}
+impl<I: FusedIterator> FusedIterator for IterIndexed<I> {}
+
+/// Iterator over every slide that can be made, as `(direction, count)` pairs for
+/// [`Puzzle::slide_towards`](crate::Puzzle::slide_towards), returned by
+/// [`Puzzle::legal_moves`](crate::Puzzle::legal_moves).
+///
+/// Directions come in the order of [`Direction::ALL`], and counts from 1 up.
+#[derive(Debug, Clone)]
+pub struct LegalMoves {
+    limits: [usize; 4],
+    direction: usize,
+    count: usize,
+}
+
+impl LegalMoves {
+    /// `limits` are how many tiles can slide towards each of [`Direction::ALL`].
+    pub fn new(limits: [usize; 4]) -> Self {
+        Self {
+            limits,
+            direction: 0,
+            count: 0,
+        }
+    }
+
+    // how many tiles there are behind `empty` in every direction, up to the edge or a cell that
+    // `stops` them
+    #[cfg(feature = "std")]
+    pub(crate) fn until(
+        (width, height): (usize, usize),
+        empty: (usize, usize),
+        stops: impl Fn((usize, usize)) -> bool,
+    ) -> Self {
+        Self::new(Direction::ALL.map(|direction| {
+            let (dx, dy): (isize, isize) = direction.into();
+            (1..)
+                .map_while(|n: isize| {
+                    let x = empty.0.checked_add_signed(-dx * n)?;
+                    let y = empty.1.checked_add_signed(-dy * n)?;
+                    (x < width && y < height && !stops((x, y))).then_some(())
+                })
+                .count()
+        }))
+    }
+
+    /// Leaves out slides towards the opposite of `previous`, which would only undo some or all
+    /// of it.
+    pub fn after(mut self, previous: Direction) -> Self {
+        let opposite = Direction::ALL
+            .iter()
+            .position(|&direction| direction == previous.opposite())
+            .expect("potential BUG: a direction is not in Direction::ALL");
+        self.limits[opposite] = 0;
+        self
+    }
+}
+
+impl Iterator for LegalMoves {
+    type Item = (Direction, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&limit) = self.limits.get(self.direction) {
+            if self.count < limit {
+                self.count += 1;
+                return Some((Direction::ALL[self.direction], self.count));
+            }
+            self.direction += 1;
+            self.count = 0;
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = match self.limits.get(self.direction..) {
+            Some([limit, rest @ ..]) => limit - self.count + rest.iter().sum::<usize>(),
+            _ => 0,
+        };
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for LegalMoves {}
+impl FusedIterator for LegalMoves {}
@@ -1,117 +1,525 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 use num::{Integer, NumCast};
-use omniswap::rotate;
+
+use core::iter::{once, Skip, StepBy, Take};
+
+#[cfg(feature = "animation")]
+mod animation;
+#[cfg(feature = "animation")]
+pub use animation::{Animation, Clip, Frame};
+
+#[cfg(feature = "std")]
+mod analysis;
+#[cfg(feature = "std")]
+pub use analysis::{Analysis, Efficiency, Pause, Phase};
+
+#[cfg(feature = "std")]
+mod ansi;
+#[cfg(feature = "std")]
+pub use ansi::{Ansi, Colors};
+
+#[cfg(feature = "std")]
+mod color;
+#[cfg(feature = "std")]
+pub use color::{Palette, Rgb, Scheme};
 
 mod direction;
-pub use direction::Direction;
+pub use direction::{Convention, Direction};
+
+mod error;
+pub use error::{ConversionError, DirectionError, NotationError, ReplayError};
+
+mod iter;
+pub use iter::{IterIndexed, LegalMoves};
+
+mod position;
+pub use position::Position;
 
 mod impls {
+    #[cfg(feature = "alloc")]
     pub mod heap;
+    #[cfg(feature = "std")]
+    pub mod loopover;
+    #[cfg(feature = "std")]
+    pub mod multi_blank;
     #[cfg(feature = "ndarray")]
     pub mod ndarray;
     pub mod stack;
+    #[cfg(feature = "alloc")]
+    pub mod tiles;
+    #[cfg(feature = "std")]
+    pub mod torus;
+    #[cfg(feature = "std")]
+    pub mod walled;
 }
+#[cfg(feature = "alloc")]
 pub use impls::heap::BoxPuzzle;
+#[cfg(feature = "std")]
+pub use impls::loopover::{Line, Loopover, Rotation};
+#[cfg(feature = "std")]
+pub use impls::multi_blank::MultiBlankPuzzle;
 #[cfg(feature = "ndarray")]
 pub use impls::ndarray::NdArrayPuzzle;
+#[doc(hidden)]
+pub use impls::stack::validate_layout;
 pub use impls::stack::StackPuzzle;
+#[cfg(feature = "alloc")]
+pub use impls::tiles::TilePuzzle;
+#[cfg(feature = "std")]
+pub use impls::torus::TorusPuzzle;
+#[cfg(feature = "std")]
+pub use impls::walled::{Reachability, Region, WalledPuzzle};
+
+#[cfg(feature = "std")]
+mod replay;
+#[cfg(feature = "std")]
+pub use replay::{AnyPuzzle, BoardKind, Move, Player, Recordable, Recorder, Replay};
+
+#[cfg(feature = "session")]
+mod session;
+#[cfg(feature = "session")]
+pub use session::{Session, Slide, Solve, Stats, Time};
+
+#[cfg(feature = "std")]
+mod solver;
+#[cfg(feature = "std")]
+pub use solver::{Solutions, Solver};
+
+#[cfg(feature = "std")]
+mod svg;
+#[cfg(feature = "std")]
+pub use svg::Svg;
+
+#[cfg(feature = "std")]
+mod symmetry;
+#[cfg(feature = "std")]
+pub use symmetry::Symmetry;
+
+#[cfg(feature = "std")]
+mod zobrist;
+#[cfg(feature = "std")]
+pub use zobrist::{Zobrist, ZobristTable};
+
+#[cfg(any(all(test, feature = "std"), feature = "testing"))]
+pub mod testing;
 
 pub trait Piece: Clone + Integer + NumCast {}
 impl<T: Clone + Integer + NumCast> Piece for T {}
 
-pub trait Puzzle<T: Piece>: core::ops::Index<(usize, usize)> {
+/// A sliding puzzle whose pieces are `T`, with `T::zero()` as the empty piece.
+///
+/// Every position is `(x, y)`, `x` being the column from the left and `y` the row from the
+/// top, and `shape()` is `(width, height)`. Indexing, [`index_of`](Self::index_of) and every
+/// iterator follow this, so `puzzle[puzzle.index_of(p).unwrap()] == p` for every piece.
+pub trait Puzzle<T: Piece>:
+    core::ops::Index<(usize, usize), Output = T> + core::ops::Index<Position, Output = T>
+{
     fn shape(&self) -> (usize, usize);
     fn index_of(&self, value: T) -> Option<(usize, usize)>;
 
+    fn get(&self, position: impl Into<Position>) -> Option<&T> {
+        let Position { x, y } = position.into();
+        let (width, height) = self.shape();
+        (x < width && y < height).then(|| &self[(x, y)])
+    }
+
     // 0 1 2
     // 3 4 5
     // 6 7 8
-    // type Iter<'a>: Iterator<Item = &'a T>
-    // where Self: 'a, T: 'a;
-    // fn iter(&self) -> Self::Iter<'_>;
+    type Iter<'a>: Iterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_>;
 
     // (0,0) (1,0) (2,0)
     // (0,1) (1,1) (2,1)
     // (0,2) (1,2) (2,2)
-    // type Iter2d: Iterator<Item = ((usize, usize), T)>;
-    // fn iter_indexed_2d(&self) -> eru -Self::Iter2d;
+    fn iter_indexed(&self) -> IterIndexed<Self::Iter<'_>> {
+        IterIndexed::new(self.iter(), self.shape().0)
+    }
+
+    fn row(&self, y: usize) -> Take<Skip<Self::Iter<'_>>> {
+        let (width, height) = self.shape();
+        assert!(y < height, "row {y} is out of bounds for height {height}");
+        self.iter().skip(y * width).take(width)
+    }
+
+    fn column(&self, x: usize) -> StepBy<Skip<Self::Iter<'_>>> {
+        let (width, _) = self.shape();
+        assert!(x < width, "column {x} is out of bounds for width {width}");
+        self.iter().skip(x).step_by(width)
+    }
+
+    fn is_solved(&self) -> bool {
+        let (width, height) = self.shape();
+        let solved = (1..width * height).chain(once(0));
+
+        self.iter()
+            .zip(solved)
+            .all(|(current, solved)| num::cast(current.clone()) == Some(solved))
+    }
 
     fn slide_from(&mut self, from: (usize, usize)) -> Option<usize>;
+
+    /// Slides `distance` tiles towards `direction`, following [`Convention::Tile`]: the tiles
+    /// go that way and the empty piece the other. See
+    /// [`slide_towards_as`](Self::slide_towards_as) for directions in the other convention.
     fn slide_towards(&mut self, direction: Direction, distance: usize) -> Option<usize> {
-        let (width, height) = self.shape();
         let zero = self
             .index_of(num::zero())
             .expect("potential BUG: could not find an empty piece");
 
         let direction: (isize, isize) = direction.into();
+        let distance = isize::try_from(distance).ok()?;
 
+        // out of bounds on the upper side is left to `slide_from`
         self.slide_from((
-            zero.0
-                .saturating_add_signed(-direction.0.saturating_mul(distance as isize))
-                .clamp(0, width),
-            zero.1
-                .saturating_add_signed(-direction.1.saturating_mul(distance as isize))
-                .clamp(0, height),
+            zero.0.checked_add_signed(-direction.0 * distance)?,
+            zero.1.checked_add_signed(-direction.1 * distance)?,
         ))
     }
+
+    /// Slides like [`slide_towards`](Self::slide_towards), with `direction` in `convention`.
+    fn slide_towards_as(
+        &mut self,
+        direction: Direction,
+        distance: usize,
+        convention: Convention,
+    ) -> Option<usize> {
+        self.slide_towards(convention.tile(direction), distance)
+    }
+
+    /// Every slide from the current empty piece, as arguments for
+    /// [`slide_towards`](Self::slide_towards). [`LegalMoves::after`] leaves out the inverse of
+    /// the previous move.
+    fn legal_moves(&self) -> LegalMoves {
+        let (width, height) = self.shape();
+        let (x, y) = self
+            .index_of(num::zero())
+            .expect("potential BUG: could not find an empty piece");
+
+        // the tiles below, above, to the right and to the left of the empty piece
+        LegalMoves::new([height - 1 - y, y, width - 1 - x, x])
+    }
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn cross_type_equality() {
+    let mut stack = StackPuzzle::default();
+    let mut boxed = BoxPuzzle::default();
+    assert_eq!(boxed, stack);
+    assert_eq!(stack, boxed);
+
+    stack.slide_from((3, 1));
+    assert_ne!(boxed, stack);
+    assert_ne!(stack, boxed);
+
+    boxed.slide_towards(Direction::Down, 2);
+    assert_eq!(boxed, stack);
+    assert_eq!(stack, boxed);
+
+    #[cfg(feature = "ndarray")]
+    {
+        let ndarray = NdArrayPuzzle::default();
+        assert_ne!(ndarray, boxed);
+        assert_ne!(ndarray, stack);
+    }
+
+    assert!(BoxPuzzle::<u8>::random((3, 3)) < BoxPuzzle::random((4, 3)));
+}
+
+#[test]
+fn iteration_works() {
+    fn check<P: Puzzle<u8>>(mut puzzle: P) {
+        let (width, height) = puzzle.shape();
+        assert!(puzzle.is_solved());
+
+        for ((x, y), piece) in puzzle.iter_indexed() {
+            assert_eq!(*piece, puzzle[(x, y)]);
+            assert_eq!(puzzle.row(y).nth(x), Some(piece));
+            assert_eq!(puzzle.column(x).nth(y), Some(piece));
+        }
+        assert_eq!(puzzle.iter().count(), width * height);
+        assert_eq!(puzzle.row(0).count(), width);
+        assert_eq!(puzzle.column(0).count(), height);
+
+        puzzle.slide_from((0, height - 1));
+        assert!(!puzzle.is_solved());
+        assert_eq!(puzzle.row(height - 1).next(), Some(&0));
+    }
+
+    #[cfg(feature = "alloc")]
+    check(BoxPuzzle::default());
+    check(StackPuzzle::default());
+    #[cfg(feature = "ndarray")]
+    check(NdArrayPuzzle::default());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn conversions_work() {
+    let mut stack = StackPuzzle::default();
+    stack.slide_from((1, 3));
+
+    let boxed = BoxPuzzle::from(stack.clone());
+    assert_eq!(boxed, stack);
+    assert_eq!(StackPuzzle::try_from(boxed.clone()), Ok(stack.clone()));
+    assert_eq!(
+        StackPuzzle::<3, 5, u8>::try_from(boxed.clone()),
+        Err(ConversionError::ShapeMismatch {
+            expected: (3, 5),
+            found: (4, 4)
+        })
+    );
+
+    #[cfg(feature = "ndarray")]
+    {
+        let ndarray = NdArrayPuzzle::from(boxed.clone());
+        assert_eq!(ndarray, boxed);
+        assert_eq!(BoxPuzzle::from(ndarray.clone()), boxed);
+        assert_eq!(NdArrayPuzzle::from(stack.clone()), ndarray);
+        assert_eq!(StackPuzzle::try_from(ndarray), Ok(stack.clone()));
+    }
+
+    let wide: BoxPuzzle<u16> = boxed.clone().cast();
+    assert_eq!(wide.try_cast::<u8>(), Ok(boxed));
+
+    let large = BoxPuzzle::<u16>::random((16, 17));
+    let (position, _) = large.iter_indexed().find(|(_, &p)| p > 255).unwrap();
+    assert_eq!(
+        large.try_cast::<u8>(),
+        Err(ConversionError::PieceOutOfRange { position })
+    );
+
+    let stack: StackPuzzle<4, 4, u64> = stack.cast();
+    assert_eq!(stack.try_cast::<i8>().map(|p| p[(0, 0)]), Ok(1));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn legal_moves_works() {
+    use std::collections::HashSet;
+
+    // what `slide_towards` accepts, tried one by one
+    fn slides<T: Piece, P: Puzzle<T> + Clone>(puzzle: &P) -> HashSet<(Direction, usize)> {
+        let (width, height) = puzzle.shape();
+        Direction::ALL
+            .into_iter()
+            .flat_map(|direction| (1..=width.max(height)).map(move |count| (direction, count)))
+            .filter(|&(direction, count)| {
+                puzzle.clone().slide_towards(direction, count) == Some(count)
+            })
+            .collect()
+    }
+    fn check<T: Piece, P: Puzzle<T> + Clone>(puzzle: &P) {
+        let moves = puzzle.legal_moves();
+        assert_eq!(moves.len(), moves.clone().count());
+        assert_eq!(moves.collect::<HashSet<_>>(), slides(puzzle));
+    }
+
+    let mut rng = rand::thread_rng();
+    for shape in [(4, 4), (3, 5), (1, 4)] {
+        check(&BoxPuzzle::<u8>::random_with_rng(&mut rng, shape));
+        check(&TorusPuzzle::<u8>::random_with_rng(&mut rng, shape));
+        check(&MultiBlankPuzzle::<u8>::random_with_rng(&mut rng, shape, 2));
+        #[cfg(feature = "ndarray")]
+        check(&NdArrayPuzzle::<u8>::random_with_rng(&mut rng, shape));
+    }
+    check(&StackPuzzle::<4, 4, u8>::default());
+    let walls = [(1, 0), (1, 1), (2, 3)];
+    check(&WalledPuzzle::<u8>::random_with_rng(
+        &mut rng,
+        (4, 4),
+        walls,
+    ));
+
+    // 1 2 3 4
+    // 5 6 7 8
+    // 9 a b c
+    // d e f
+    let puzzle = StackPuzzle::<4, 4, u8>::default();
+    let moves = puzzle.legal_moves().collect::<Vec<_>>();
+    use Direction::*;
+    assert_eq!(
+        moves,
+        [
+            (Down, 1),
+            (Down, 2),
+            (Down, 3),
+            (Right, 1),
+            (Right, 2),
+            (Right, 3)
+        ]
+    );
+    assert_eq!(puzzle.legal_moves().after(Left).len(), 3);
+    assert_eq!(puzzle.legal_moves().after(Down).len(), 6);
+}
+
+#[cfg(feature = "alloc")]
 #[test]
 fn is_solvable_works() {
-    assert_eq!(is_solvable(&[1, 2, 3, 4, 5, 6, 7, 8, 0], 3), true);
-    assert_eq!(is_solvable(&[2, 4, 8, 7, 6, 5, 3, 0, 1], 3), true);
-    assert_eq!(is_solvable(&[2, 1, 3, 4, 8, 5, 0, 6, 7], 3), true);
-    assert_eq!(is_solvable(&[1, 2, 3, 4, 5, 0, 7, 6, 8], 3), false);
-    assert_eq!(is_solvable(&[2, 4, 8, 7, 0, 5, 3, 1, 6], 3), false);
-    assert_eq!(is_solvable(&[2, 1, 3, 0, 8, 5, 4, 7, 6], 3), false);
+    assert!(is_solvable(&[1, 2, 3, 4, 5, 6, 7, 8, 0], 3));
+    assert!(is_solvable(&[2, 4, 8, 7, 6, 5, 3, 0, 1], 3));
+    assert!(is_solvable(&[2, 1, 3, 4, 8, 5, 0, 6, 7], 3));
+    assert!(!is_solvable(&[1, 2, 3, 4, 5, 0, 7, 6, 8], 3));
+    assert!(!is_solvable(&[2, 4, 8, 7, 0, 5, 3, 1, 6], 3));
+    assert!(!is_solvable(&[2, 1, 3, 0, 8, 5, 4, 7, 6], 3));
+
+    assert!(is_solvable(&[0], 1));
+    assert!(is_solvable(&[1, 0, 2, 3], 4));
+    assert!(!is_solvable(&[2, 0, 1, 3], 1));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn is_solvable_matches_reachability() {
+    // every board of up to 8 pieces, against what is reachable from the solved one
+    use itertools::Itertools;
+    use std::collections::{HashSet, VecDeque};
+
+    let mut rng = rand::thread_rng();
+    for (width, height) in (1..=8).flat_map(|w| (1..=8 / w).map(move |h| (w, h))) {
+        let len = width * height;
+        let solved = (1..len as u8).chain(once(0)).collect::<Vec<_>>();
+        let solved = BoxPuzzle::from_pieces((width, height), solved).unwrap();
+        let mut reachable = HashSet::from([solved.clone()]);
+        let mut queue = VecDeque::from([solved]);
+        while let Some(puzzle) = queue.pop_front() {
+            for direction in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                let mut next = puzzle.clone();
+                if next.slide_towards(direction, 1) == Some(1) && reachable.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        for pieces in (0..len as u8).permutations(len) {
+            let puzzle = BoxPuzzle::from_pieces((width, height), pieces.clone()).unwrap();
+            assert_eq!(
+                is_solvable(&pieces, width),
+                reachable.contains(&puzzle),
+                "{width}x{height}: {pieces:?}"
+            );
+        }
+
+        for _ in 0..100 {
+            let puzzle = BoxPuzzle::random_with_rng(&mut rng, (width, height));
+            assert!(reachable.contains(&puzzle), "{width}x{height}: {puzzle:?}");
+            #[cfg(feature = "ndarray")]
+            {
+                let puzzle = NdArrayPuzzle::random_with_rng(&mut rng, (width, height));
+                assert!(reachable.contains(&BoxPuzzle::from(puzzle)));
+            }
+            if (width, height) == (3, 2) {
+                let puzzle = StackPuzzle::<3, 2, u8>::random_with_rng(&mut rng);
+                assert!(reachable.contains(&BoxPuzzle::from(puzzle)));
+            }
+        }
+    }
 }
 
-#[allow(dead_code)]
+#[cfg(feature = "alloc")]
 pub(crate) fn is_solvable<T: Piece>(pieces: &[T], width: usize) -> bool {
-    debug_assert!(width >= 2);
-    debug_assert!(pieces.len() >= 4);
-    debug_assert!(pieces.len() % width == 0);
-
-    let mut pieces = pieces
-        .iter()
-        .cloned()
-        .map(num::cast)
-        .collect::<Option<Vec<usize>>>()
-        .expect("could not cast pieces to usize");
-
-    let height = pieces.len() / width;
-    let last_idx = pieces.len() - 1;
-    let Some(empty_idx) = pieces.iter().position(|&p| p == 0) else {
+    is_solvable_with(pieces, width, &mut alloc::vec![false; pieces.len()])
+}
+
+// `is_solvable` without allocating, with `visited` as scratch space as long as `pieces`
+pub(crate) fn is_solvable_with<T: Piece>(pieces: &[T], width: usize, visited: &mut [bool]) -> bool {
+    debug_assert!(width >= 1);
+    debug_assert_eq!(pieces.len() % width, 0);
+    debug_assert_eq!(visited.len(), pieces.len());
+
+    let len = pieces.len();
+    let height = len / width;
+    // where every piece belongs, the empty piece last
+    let goal = |piece: &T| -> usize {
+        let piece: usize = num::cast(piece.clone()).expect("could not cast pieces to usize");
+        (piece + len - 1) % len
+    };
+
+    let Some(empty_idx) = pieces.iter().position(|p| p.is_zero()) else {
         return false;
     };
     let empty_pos = (empty_idx % width, empty_idx / width);
 
-    match (width - 1 - empty_pos.0) + (height - 1 - empty_pos.1) {
-        0 => {}
-        d if d.is_odd() => {
-            // odd, just swap with the last piece
-            pieces.swap(empty_idx, last_idx);
+    // a single row or column never changes the order of its tiles
+    if width == 1 || height == 1 {
+        return pieces
+            .iter()
+            .filter(|p| !p.is_zero())
+            .enumerate()
+            .all(|(idx, p)| goal(p) == idx);
+    }
+
+    // every slide is a transposition that moves the empty piece by one cell, so the parity of
+    // the permutation has to match the empty piece's distance from the bottom-right corner
+    let distance = (width - 1 - empty_pos.0) + (height - 1 - empty_pos.1);
+
+    // a permutation of `len` pieces in `cycles` cycles takes `len - cycles` swaps
+    visited.fill(false);
+    let mut cycles: usize = 0;
+    for start in 0..len {
+        if visited[start] {
+            continue;
         }
-        _ => {
-            // even, also swap with the second last piece
-            rotate!(
-                &mut pieces[empty_idx],
-                &mut pieces[last_idx - 1],
-                &mut pieces[last_idx]
-            );
+        cycles += 1;
+
+        let mut idx = start;
+        while !visited[idx] {
+            visited[idx] = true;
+            idx = goal(&pieces[idx]);
         }
     }
 
-    let mut swaps: usize = 0;
-    for i in 0..=pieces.len() - 3 {
-        loop {
-            let j = pieces[i] - 1;
-            if i == j {
-                break;
-            }
-            pieces.swap(i, j);
-            swaps += 1;
+    (len - cycles).is_even() == distance.is_even()
+}
+
+// fills `pieces` with a random solvable board `width` wide, row by row, with `visited` as in
+// `is_solvable_with`
+pub(crate) fn random_pieces<T: Piece>(
+    rng: &mut (impl rand::Rng + ?Sized),
+    pieces: &mut [T],
+    width: usize,
+    visited: &mut [bool],
+) {
+    use rand::seq::SliceRandom;
+
+    let len = pieces.len();
+    let cast = |piece: usize| -> T { num::cast(piece).expect("could not cast pieces to usize") };
+
+    if width == 1 || width == len {
+        // the tiles stay in order, only the empty piece can be anywhere
+        let empty_idx = rng.gen_range(0..len);
+        for (idx, piece) in pieces.iter_mut().enumerate() {
+            *piece = match idx.cmp(&empty_idx) {
+                core::cmp::Ordering::Less => cast(idx + 1),
+                core::cmp::Ordering::Equal => T::zero(),
+                core::cmp::Ordering::Greater => cast(idx),
+            };
         }
+        return;
     }
 
-    swaps.is_even()
+    for (idx, piece) in pieces.iter_mut().enumerate() {
+        *piece = cast((idx + 1) % len);
+    }
+    pieces.shuffle(rng);
+    if !is_solvable_with(pieces, width, visited) {
+        let mut tiles = pieces.iter_mut().filter(|piece| !piece.is_zero());
+        let (Some(a), Some(b)) = (tiles.next(), tiles.next()) else {
+            unreachable!("potential BUG: a board of two rows and columns has three tiles");
+        };
+        core::mem::swap(a, b);
+    }
 }
@@ -0,0 +1,26 @@
+/// A cell of a puzzle: `x` is the column from the left, `y` the row from the top.
+///
+/// Converts to and from the `(x, y)` tuples taken by [`Puzzle`](crate::Puzzle).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Position {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Position {
+    pub const fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<(usize, usize)> for Position {
+    fn from((x, y): (usize, usize)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<Position> for (usize, usize) {
+    fn from(Position { x, y }: Position) -> Self {
+        (x, y)
+    }
+}
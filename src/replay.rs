@@ -0,0 +1,637 @@
+use core::ops::{Deref, Index};
+use core::time::Duration;
+use std::time::Instant;
+
+use crate::{
+    BoxPuzzle, ConversionError, Direction, LegalMoves, MultiBlankPuzzle, Piece, Position, Puzzle,
+    ReplayError, StackPuzzle, TilePuzzle, TorusPuzzle, WalledPuzzle, Zobrist,
+};
+
+/// A recorded solve: the starting board and every move made from it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Replay {
+    pub shape: (usize, usize),
+    /// The pieces of the starting board, row by row.
+    pub board: Vec<usize>,
+    /// What the board is, and so what it is played back on.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: BoardKind,
+    pub moves: Vec<Move>,
+}
+
+/// The kinds of board a [`Player`] can play a [`Replay`] back on.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoardKind {
+    /// A [`BoxPuzzle`], or anything that slides like one.
+    #[default]
+    Plain,
+    /// A [`TorusPuzzle`], where slides wrap around the edges.
+    Torus,
+    /// A [`MultiBlankPuzzle`], the blanks being the zeros of the board.
+    MultiBlank,
+    /// A [`WalledPuzzle`] with walls where `walls` says, row by row.
+    Walled { walls: Vec<bool> },
+}
+
+/// A puzzle a [`Recorder`] can record, which tells what kind of board it is.
+///
+/// Boards that slide like a [`BoxPuzzle`] only need an empty impl.
+pub trait Recordable<T: Piece>: Puzzle<T> {
+    fn board_kind(&self) -> BoardKind {
+        BoardKind::Plain
+    }
+}
+
+impl<T: Piece> Recordable<T> for BoxPuzzle<T> {}
+
+impl<const W: usize, const H: usize, T: Piece> Recordable<T> for StackPuzzle<W, H, T> {}
+
+#[cfg(feature = "ndarray")]
+impl<T: Piece> Recordable<T> for crate::NdArrayPuzzle<T> {}
+
+/// Played back tile by tile, so a board solved only up to classes does not verify.
+impl<M> Recordable<usize> for TilePuzzle<M> {}
+
+impl<T: Piece> Recordable<T> for TorusPuzzle<T> {
+    fn board_kind(&self) -> BoardKind {
+        BoardKind::Torus
+    }
+}
+
+impl<T: Piece> Recordable<T> for MultiBlankPuzzle<T> {
+    fn board_kind(&self) -> BoardKind {
+        BoardKind::MultiBlank
+    }
+}
+
+impl<T: Piece> Recordable<T> for WalledPuzzle<T> {
+    fn board_kind(&self) -> BoardKind {
+        let (width, height) = self.shape();
+        let mut walls = vec![false; width * height];
+        for (x, y) in self.walls() {
+            walls[y * width + x] = true;
+        }
+        BoardKind::Walled { walls }
+    }
+}
+
+impl<T: Piece, P: Recordable<T>> Recordable<T> for Zobrist<'_, P> {
+    fn board_kind(&self) -> BoardKind {
+        (**self).board_kind()
+    }
+}
+
+impl Recordable<usize> for AnyPuzzle {
+    fn board_kind(&self) -> BoardKind {
+        match self {
+            AnyPuzzle::Plain(_) => BoardKind::Plain,
+            AnyPuzzle::Torus(_) => BoardKind::Torus,
+            AnyPuzzle::MultiBlank(_) => BoardKind::MultiBlank,
+            AnyPuzzle::Walled(puzzle) => puzzle.board_kind(),
+        }
+    }
+}
+
+/// One [`Puzzle::slide_from`] of a [`Replay`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    /// Where the slid tile was, i.e. what was given to [`Puzzle::slide_from`].
+    pub position: (usize, usize),
+    /// Where the tiles went.
+    pub direction: Direction,
+    /// How many tiles moved.
+    pub count: usize,
+    /// Milliseconds since the recording started.
+    pub time: u64,
+}
+
+impl Replay {
+    pub fn new<T: Piece, P: Recordable<T>>(board: &P) -> Self {
+        Self {
+            shape: board.shape(),
+            board: board
+                .iter()
+                .cloned()
+                .map(num::cast)
+                .collect::<Option<_>>()
+                .expect("could not cast pieces to usize"),
+            kind: board.board_kind(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// How long the solve took, from the start of the recording to the last move.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.moves.last().map_or(0, |last| last.time))
+    }
+
+    /// Plays every move back and checks that they solve the puzzle.
+    pub fn verify(&self) -> Result<(), ReplayError> {
+        Player::new(self)?.play().map(drop)
+    }
+}
+
+/// A puzzle that records every slide into a [`Replay`], through
+/// [`Puzzle::slide_towards`] as much as [`Puzzle::slide_from`], which both go to the puzzle it
+/// wraps. Any [`Recordable`] puzzle can be wrapped, and played back on the same kind of board.
+///
+/// Timestamps count from when the recorder was created, unless given with
+/// [`slide_from_at`](Self::slide_from_at) or [`slide_towards_at`](Self::slide_towards_at).
+#[derive(Debug, Clone)]
+pub struct Recorder<P> {
+    puzzle: P,
+    replay: Replay,
+    start: Instant,
+}
+
+impl<P> Recorder<P> {
+    pub fn new<T: Piece>(puzzle: P) -> Self
+    where
+        P: Recordable<T>,
+    {
+        Self {
+            replay: Replay::new(&puzzle),
+            puzzle,
+            start: Instant::now(),
+        }
+    }
+
+    /// Slides like [`Puzzle::slide_from`], recording the move as made at `time`.
+    pub fn slide_from_at<T: Piece>(&mut self, from: (usize, usize), time: Duration) -> Option<usize>
+    where
+        P: Puzzle<T>,
+    {
+        let piece = self.puzzle.get(from)?.clone();
+        let count = self.puzzle.slide_from(from)?;
+        if count == 0 {
+            return Some(0);
+        }
+
+        // the slid tile is next to where it was, on the board if it can be, or round the edge
+        // of one that wraps
+        let shape = self.puzzle.shape();
+        let (width, height) = shape;
+        let next = |direction: Direction| {
+            let (dx, dy): (isize, isize) = direction.into();
+            let (x, y) = (
+                from.0.checked_add_signed(dx)?,
+                from.1.checked_add_signed(dy)?,
+            );
+            (x < width && y < height).then_some((x, y))
+        };
+        let direction = Direction::ALL
+            .into_iter()
+            .find(|&direction| next(direction).is_some_and(|next| self.puzzle[next] == piece))
+            .or_else(|| {
+                Direction::ALL
+                    .into_iter()
+                    .find(|&direction| self.puzzle[offset(from, direction, 1, shape)] == piece)
+            })
+            .expect("potential BUG: the slid tile is not next to where it was");
+
+        self.record(from, direction, count, time);
+        Some(count)
+    }
+
+    /// Slides like [`Puzzle::slide_towards`], recording the move as made at `time`.
+    pub fn slide_towards_at<T: Piece>(
+        &mut self,
+        direction: Direction,
+        distance: usize,
+        time: Duration,
+    ) -> Option<usize>
+    where
+        P: Puzzle<T>,
+    {
+        let empty = self
+            .puzzle
+            .index_of(T::zero())
+            .expect("potential BUG: could not find an empty piece");
+        let count = self.puzzle.slide_towards(direction, distance)?;
+        if count == 0 {
+            return Some(0);
+        }
+
+        let from = offset(empty, direction.opposite(), count, self.puzzle.shape());
+        self.record(from, direction, count, time);
+        Some(count)
+    }
+
+    fn record(
+        &mut self,
+        position: (usize, usize),
+        direction: Direction,
+        count: usize,
+        time: Duration,
+    ) {
+        self.replay.moves.push(Move {
+            position,
+            direction,
+            count,
+            time: time.as_millis() as u64,
+        });
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn into_replay(self) -> Replay {
+        self.replay
+    }
+
+    pub fn into_inner(self) -> P {
+        self.puzzle
+    }
+}
+
+impl<P> Deref for Recorder<P> {
+    type Target = P;
+    fn deref(&self) -> &Self::Target {
+        &self.puzzle
+    }
+}
+
+impl<I, P: Index<I>> Index<I> for Recorder<P> {
+    type Output = P::Output;
+    fn index(&self, idx: I) -> &Self::Output {
+        &self.puzzle[idx]
+    }
+}
+
+impl<T: Piece, P: Puzzle<T>> Puzzle<T> for Recorder<P> {
+    type Iter<'a>
+        = P::Iter<'a>
+    where
+        Self: 'a,
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.puzzle.iter()
+    }
+
+    #[inline]
+    fn shape(&self) -> (usize, usize) {
+        self.puzzle.shape()
+    }
+
+    fn index_of(&self, value: T) -> Option<(usize, usize)> {
+        self.puzzle.index_of(value)
+    }
+
+    fn is_solved(&self) -> bool {
+        self.puzzle.is_solved()
+    }
+
+    fn legal_moves(&self) -> LegalMoves {
+        self.puzzle.legal_moves()
+    }
+
+    fn slide_from(&mut self, from: (usize, usize)) -> Option<usize> {
+        self.slide_from_at(from, self.start.elapsed())
+    }
+
+    fn slide_towards(&mut self, direction: Direction, distance: usize) -> Option<usize> {
+        self.slide_towards_at(direction, distance, self.start.elapsed())
+    }
+}
+
+/// Plays a [`Replay`] back one move at a time, checking every move on the way.
+#[derive(Debug, Clone)]
+pub struct Player<'a> {
+    replay: &'a Replay,
+    puzzle: AnyPuzzle,
+    next: usize,
+}
+
+impl<'a> Player<'a> {
+    pub fn new(replay: &'a Replay) -> Result<Self, ReplayError> {
+        let puzzle = AnyPuzzle::new(replay.shape, replay.board.clone(), &replay.kind)
+            .map_err(ReplayError::InvalidBoard)?;
+
+        Ok(Self {
+            replay,
+            puzzle,
+            next: 0,
+        })
+    }
+
+    /// The board as of the last move played.
+    pub fn puzzle(&self) -> &AnyPuzzle {
+        &self.puzzle
+    }
+
+    /// Plays the next move, or returns `None` after the last one.
+    pub fn step(&mut self) -> Option<Result<&'a Move, ReplayError>> {
+        let index = self.next;
+        let next = self.replay.moves.get(index)?;
+        self.next += 1;
+
+        let previous = index.checked_sub(1).map(|index| &self.replay.moves[index]);
+        if previous.is_some_and(|previous| previous.time > next.time) {
+            return Some(Err(ReplayError::Unordered { index }));
+        }
+
+        Some(match self.puzzle.play(next) {
+            true => Ok(next),
+            false => Err(ReplayError::IllegalMove { index }),
+        })
+    }
+
+    /// Plays every remaining move, returning the final board if it is solved.
+    pub fn play(mut self) -> Result<AnyPuzzle, ReplayError> {
+        while let Some(step) = self.step() {
+            step?;
+        }
+
+        match self.puzzle.is_solved() {
+            true => Ok(self.puzzle),
+            false => Err(ReplayError::NotSolved),
+        }
+    }
+}
+
+/// Any of the boards a [`Replay`] can be played back on, see [`BoardKind`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AnyPuzzle {
+    Plain(BoxPuzzle<usize>),
+    Torus(TorusPuzzle<usize>),
+    MultiBlank(MultiBlankPuzzle<usize>),
+    Walled(WalledPuzzle<usize>),
+}
+
+impl AnyPuzzle {
+    pub fn new(
+        shape: (usize, usize),
+        pieces: Vec<usize>,
+        kind: &BoardKind,
+    ) -> Result<Self, ConversionError> {
+        Ok(match kind {
+            BoardKind::Plain => AnyPuzzle::Plain(BoxPuzzle::from_pieces(shape, pieces)?),
+            BoardKind::Torus => AnyPuzzle::Torus(TorusPuzzle::from_pieces(shape, pieces)?),
+            BoardKind::MultiBlank => {
+                AnyPuzzle::MultiBlank(MultiBlankPuzzle::from_pieces(shape, pieces)?)
+            }
+            BoardKind::Walled { walls } => {
+                AnyPuzzle::Walled(WalledPuzzle::from_pieces(shape, pieces, walls.clone())?)
+            }
+        })
+    }
+
+    // makes `next` if it slides exactly as recorded, into the blank it says
+    pub(crate) fn play(&mut self, next: &Move) -> bool {
+        let (dx, dy): (isize, isize) = next.direction.into();
+        let blank = isize::try_from(next.count).ok().and_then(|count| {
+            Some((
+                next.position.0.checked_add_signed(dx.checked_mul(count)?)?,
+                next.position.1.checked_add_signed(dy.checked_mul(count)?)?,
+            ))
+        });
+        let slid = match (self, blank) {
+            // the way recorded, which need not be the shorter one `slide_from` would take
+            (AnyPuzzle::Torus(puzzle), _) => {
+                let (width, height) = puzzle.shape();
+                let count = next.count % (width * height);
+                let blank = offset(next.position, next.direction, count, (width, height));
+                let inside = next.position.0 < width && next.position.1 < height;
+                match inside && puzzle.index_of(0) == Some(blank) {
+                    true => puzzle.slide_towards(next.direction, next.count),
+                    false => None,
+                }
+            }
+            (_, None) => None,
+            (AnyPuzzle::MultiBlank(puzzle), Some(blank)) => puzzle.slide_into(next.position, blank),
+            (puzzle, blank) if puzzle.index_of(0) == blank => puzzle.slide_from(next.position),
+            _ => None,
+        };
+        next.count > 0 && slid == Some(next.count)
+    }
+}
+
+impl Index<(usize, usize)> for AnyPuzzle {
+    type Output = usize;
+    fn index(&self, position: (usize, usize)) -> &Self::Output {
+        match self {
+            AnyPuzzle::Plain(puzzle) => &puzzle[position],
+            AnyPuzzle::Torus(puzzle) => &puzzle[position],
+            AnyPuzzle::MultiBlank(puzzle) => &puzzle[position],
+            AnyPuzzle::Walled(puzzle) => &puzzle[position],
+        }
+    }
+}
+
+impl Index<Position> for AnyPuzzle {
+    type Output = usize;
+    fn index(&self, position: Position) -> &Self::Output {
+        &self[(position.x, position.y)]
+    }
+}
+
+impl Puzzle<usize> for AnyPuzzle {
+    type Iter<'a> = core::slice::Iter<'a, usize>;
+    fn iter(&self) -> Self::Iter<'_> {
+        match self {
+            AnyPuzzle::Plain(puzzle) => puzzle.iter(),
+            AnyPuzzle::Torus(puzzle) => puzzle.iter(),
+            AnyPuzzle::MultiBlank(puzzle) => puzzle.iter(),
+            AnyPuzzle::Walled(puzzle) => puzzle.iter(),
+        }
+    }
+
+    fn shape(&self) -> (usize, usize) {
+        match self {
+            AnyPuzzle::Plain(puzzle) => puzzle.shape(),
+            AnyPuzzle::Torus(puzzle) => puzzle.shape(),
+            AnyPuzzle::MultiBlank(puzzle) => puzzle.shape(),
+            AnyPuzzle::Walled(puzzle) => puzzle.shape(),
+        }
+    }
+
+    fn index_of(&self, value: usize) -> Option<(usize, usize)> {
+        match self {
+            AnyPuzzle::Plain(puzzle) => puzzle.index_of(value),
+            AnyPuzzle::Torus(puzzle) => puzzle.index_of(value),
+            AnyPuzzle::MultiBlank(puzzle) => puzzle.index_of(value),
+            AnyPuzzle::Walled(puzzle) => puzzle.index_of(value),
+        }
+    }
+
+    fn is_solved(&self) -> bool {
+        match self {
+            AnyPuzzle::Plain(puzzle) => puzzle.is_solved(),
+            AnyPuzzle::Torus(puzzle) => puzzle.is_solved(),
+            AnyPuzzle::MultiBlank(puzzle) => puzzle.is_solved(),
+            AnyPuzzle::Walled(puzzle) => puzzle.is_solved(),
+        }
+    }
+
+    fn slide_from(&mut self, from: (usize, usize)) -> Option<usize> {
+        match self {
+            AnyPuzzle::Plain(puzzle) => puzzle.slide_from(from),
+            AnyPuzzle::Torus(puzzle) => puzzle.slide_from(from),
+            AnyPuzzle::MultiBlank(puzzle) => puzzle.slide_from(from),
+            AnyPuzzle::Walled(puzzle) => puzzle.slide_from(from),
+        }
+    }
+
+    fn slide_towards(&mut self, direction: Direction, distance: usize) -> Option<usize> {
+        match self {
+            AnyPuzzle::Plain(puzzle) => puzzle.slide_towards(direction, distance),
+            AnyPuzzle::Torus(puzzle) => puzzle.slide_towards(direction, distance),
+            AnyPuzzle::MultiBlank(puzzle) => puzzle.slide_towards(direction, distance),
+            AnyPuzzle::Walled(puzzle) => puzzle.slide_towards(direction, distance),
+        }
+    }
+
+    fn legal_moves(&self) -> LegalMoves {
+        match self {
+            AnyPuzzle::Plain(puzzle) => puzzle.legal_moves(),
+            AnyPuzzle::Torus(puzzle) => puzzle.legal_moves(),
+            AnyPuzzle::MultiBlank(puzzle) => puzzle.legal_moves(),
+            AnyPuzzle::Walled(puzzle) => puzzle.legal_moves(),
+        }
+    }
+}
+
+// the cell `count` cells away from `position` towards `direction`, wrapping around the edges
+pub(crate) fn offset(
+    (x, y): (usize, usize),
+    direction: Direction,
+    count: usize,
+    (width, height): (usize, usize),
+) -> (usize, usize) {
+    let (dx, dy): (isize, isize) = direction.into();
+    let count = count as isize;
+    (
+        (x as isize + dx * count).rem_euclid(width as isize) as usize,
+        (y as isize + dy * count).rem_euclid(height as isize) as usize,
+    )
+}
+
+#[test]
+fn replays_work() {
+    use crate::{Direction::*, MultiBlankPuzzle, StackPuzzle, TorusPuzzle, WalledPuzzle};
+
+    let mut recorder = Recorder::new(StackPuzzle::default());
+    assert_eq!(
+        recorder.slide_from_at((3, 0), Duration::from_millis(100)),
+        Some(3)
+    );
+    assert_eq!(
+        recorder.slide_from_at((0, 1), Duration::from_millis(250)),
+        None
+    );
+    assert_eq!(
+        recorder.slide_from_at((3, 0), Duration::from_millis(300)),
+        Some(0)
+    );
+    assert_eq!(
+        recorder.slide_from_at((0, 0), Duration::from_millis(400)),
+        Some(3)
+    );
+    assert_eq!(
+        recorder.slide_from_at((1, 0), Duration::from_millis(500)),
+        Some(1)
+    );
+
+    let replay = recorder.into_replay();
+    assert_eq!(replay.board, (1..16).chain([0]).collect::<Vec<_>>());
+    assert_eq!(
+        replay.moves[..2],
+        [
+            Move {
+                position: (3, 0),
+                direction: Down,
+                count: 3,
+                time: 100
+            },
+            Move {
+                position: (0, 0),
+                direction: Right,
+                count: 3,
+                time: 400
+            }
+        ]
+    );
+    assert_eq!(replay.moves[2].direction, Left);
+    assert_eq!(replay.duration(), Duration::from_millis(500));
+    assert_eq!(replay.verify(), Err(ReplayError::NotSolved));
+
+    // undoing everything solves it again
+    let mut player = Player::new(&replay).unwrap();
+    while let Some(step) = player.step() {
+        step.unwrap();
+    }
+    let mut recorder = Recorder::new(player.puzzle().clone());
+    recorder.slide_towards(Right, 1);
+    recorder.slide_from((3, 0));
+    recorder.slide_from((3, 3));
+    let undone = recorder.into_replay();
+    assert_eq!(undone.verify(), Ok(()));
+    assert_eq!(undone.moves.len(), 3);
+    assert!(undone
+        .moves
+        .windows(2)
+        .all(|moves| moves[0].time <= moves[1].time));
+
+    let mut broken = undone.clone();
+    broken.moves[1].direction = Up;
+    assert_eq!(broken.verify(), Err(ReplayError::IllegalMove { index: 1 }));
+    let mut broken = undone.clone();
+    broken.moves[2].count = 1;
+    assert_eq!(broken.verify(), Err(ReplayError::IllegalMove { index: 2 }));
+    let mut broken = undone.clone();
+    broken.moves[0].time = u64::MAX;
+    assert_eq!(broken.verify(), Err(ReplayError::Unordered { index: 1 }));
+    let mut broken = undone;
+    broken.board.swap(0, 1);
+    broken.board[2] = 1;
+    assert!(matches!(broken.verify(), Err(ReplayError::InvalidBoard(_))));
+
+    // played back on the same kind of board: into the second of two blanks, and round a wall
+    let mut recorder = Recorder::new(MultiBlankPuzzle::<u8>::solved((3, 3), 2));
+    assert_eq!(recorder.slide_from((2, 1)), Some(1));
+    assert_eq!(recorder.slide_towards(Up, 1), Some(1));
+    let replay = recorder.into_replay();
+    assert_eq!(replay.kind, BoardKind::MultiBlank);
+    assert_eq!(replay.moves[0].direction, Down);
+    assert_eq!(replay.verify(), Ok(()));
+
+    let mut recorder = Recorder::new(WalledPuzzle::<u8>::solved((3, 3), [(1, 1)]));
+    assert_eq!(recorder.slide_from((2, 0)), Some(2));
+    assert_eq!(recorder.slide_towards(Up, 2), Some(2));
+    let replay = recorder.into_replay();
+    assert!(matches!(replay.kind, BoardKind::Walled { ref walls } if walls[4]));
+    assert_eq!(replay.verify(), Ok(()));
+    let mut broken = replay.clone();
+    broken.kind = BoardKind::Plain;
+    assert!(matches!(broken.verify(), Err(ReplayError::InvalidBoard(_))));
+
+    // the way the puzzle went, also when it wraps around
+    let mut recorder = Recorder::new(TorusPuzzle::<u8>::solved((4, 3)));
+    assert_eq!(recorder.slide_towards(Left, 1), Some(1));
+    assert_eq!(recorder.slide_towards(Right, 3), Some(3));
+    assert_eq!(recorder.slide_from((1, 0)), Some(1));
+    assert_eq!(
+        recorder
+            .replay()
+            .moves
+            .iter()
+            .map(|next| (next.position, next.direction, next.count))
+            .collect::<Vec<_>>(),
+        [((0, 2), Left, 1), ((1, 2), Right, 3), ((1, 0), Up, 1)]
+    );
+    let replay = recorder.replay();
+    assert_eq!(replay.kind, BoardKind::Torus);
+    let mut player = Player::new(replay).unwrap();
+    while let Some(step) = player.step() {
+        step.unwrap();
+    }
+    assert!(player
+        .puzzle()
+        .iter()
+        .map(|&piece| piece as u8)
+        .eq(recorder.iter().copied()));
+}
@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+
+use core::fmt::{self, Display};
+use core::time::Duration;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::{fs, io};
+
+use crate::{Direction, Piece, Puzzle};
+
+/// The result of a solve, or of an average of solves. DNFs are worse than any time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Time {
+    Finished(Duration),
+    Dnf,
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Time::Finished(time) = self else {
+            return write!(f, "DNF");
+        };
+
+        let millis = time.as_millis();
+        match millis / 60_000 {
+            0 => write!(f, "{}.{:03}", millis / 1000, millis % 1000),
+            minutes => write!(
+                f,
+                "{minutes}:{:02}.{:03}",
+                millis / 1000 % 60,
+                millis % 1000
+            ),
+        }
+    }
+}
+
+/// A single solve: what was solved, how, and how long it took.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Solve {
+    pub shape: (usize, usize),
+    /// The pieces of the scramble, row by row.
+    pub scramble: Vec<usize>,
+    /// Every slide, in the order they were made.
+    pub solution: Vec<Slide>,
+    pub time: Duration,
+    pub dnf: bool,
+}
+
+impl Solve {
+    pub fn new<T: Piece, P: Puzzle<T>>(scramble: &P, solution: Vec<Slide>, time: Duration) -> Self {
+        let pieces = scramble
+            .iter()
+            .cloned()
+            .map(num::cast)
+            .collect::<Option<_>>()
+            .expect("could not cast pieces to usize");
+
+        Self {
+            shape: scramble.shape(),
+            scramble: pieces,
+            solution,
+            time,
+            dnf: false,
+        }
+    }
+
+    pub fn result(&self) -> Time {
+        match self.dnf {
+            true => Time::Dnf,
+            false => Time::Finished(self.time),
+        }
+    }
+
+    /// Move count in the single-tile metric, where every tile moved counts.
+    pub fn stm(&self) -> usize {
+        self.solution.iter().map(|slide| slide.count).sum()
+    }
+
+    /// Move count in the multi-tile metric, where every slide counts once however many tiles
+    /// it moved.
+    pub fn mtm(&self) -> usize {
+        self.solution.len()
+    }
+
+    /// Moves per second, in the single-tile metric, or `None` if the solve took no time at all.
+    pub fn tps(&self) -> Option<f64> {
+        (!self.time.is_zero()).then(|| self.stm() as f64 / self.time.as_secs_f64())
+    }
+}
+
+/// One slide of a [`Solve`]: `count` tiles moving together towards `direction`, in the same
+/// sense as [`Puzzle::slide_towards`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Slide {
+    pub direction: Direction,
+    pub count: usize,
+}
+
+impl Slide {
+    pub fn new(direction: Direction, count: usize) -> Self {
+        Self { direction, count }
+    }
+}
+
+/// Every solve of a practice session, possibly on several board sizes, and their statistics.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    solves: Vec<Solve>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a session saved with [`save`](Self::save), or starts a new one if there is no
+    /// such file yet.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read(path) {
+            Ok(json) => Ok(serde_json::from_slice(&json)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec(self)?)
+    }
+
+    pub fn push(&mut self, solve: Solve) {
+        self.solves.push(solve);
+    }
+
+    pub fn solves(&self) -> &[Solve] {
+        &self.solves
+    }
+
+    /// Every board size solved so far, smallest first.
+    pub fn shapes(&self) -> BTreeSet<(usize, usize)> {
+        self.solves.iter().map(|solve| solve.shape).collect()
+    }
+
+    /// Statistics over the solves of one board size.
+    pub fn stats(&self, shape: (usize, usize)) -> Stats<'_> {
+        Stats {
+            solves: self
+                .solves
+                .iter()
+                .filter(|solve| solve.shape == shape)
+                .collect(),
+        }
+    }
+
+    /// The best single of every board size solved so far.
+    pub fn personal_bests(&self) -> impl Iterator<Item = ((usize, usize), Time)> + '_ {
+        self.shapes()
+            .into_iter()
+            .filter_map(|shape| Some((shape, self.stats(shape).best()?)))
+    }
+}
+
+/// Statistics over solves of the same size, oldest first.
+#[derive(Debug, Clone)]
+pub struct Stats<'a> {
+    solves: Vec<&'a Solve>,
+}
+
+impl<'a> Stats<'a> {
+    pub fn solves(&self) -> &[&'a Solve] {
+        &self.solves
+    }
+
+    pub fn best(&self) -> Option<Time> {
+        self.solves.iter().map(|solve| solve.result()).min()
+    }
+
+    pub fn worst(&self) -> Option<Time> {
+        self.solves.iter().map(|solve| solve.result()).max()
+    }
+
+    /// Mean of every finished solve, leaving DNFs out.
+    pub fn mean(&self) -> Option<Duration> {
+        let times = self
+            .solves
+            .iter()
+            .filter(|solve| !solve.dnf)
+            .map(|solve| solve.time)
+            .collect::<Vec<_>>();
+
+        (!times.is_empty()).then(|| times.iter().sum::<Duration>() / times.len() as u32)
+    }
+
+    /// The average of the last `n` solves, or `None` if there are not that many yet.
+    ///
+    /// The best and worst 5% of the solves, and at least one of each for `n >= 3`, are left
+    /// out, so ao5 and ao12 drop one on each side and ao100 drops five. The average is a DNF
+    /// if more solves than that are DNFs.
+    pub fn average_of(&self, n: usize) -> Option<Time> {
+        let last = self.solves.len().checked_sub(n).filter(|_| n > 0)?;
+        Some(average(&self.solves[last..]))
+    }
+
+    /// The best average of `n` consecutive solves so far.
+    pub fn best_average_of(&self, n: usize) -> Option<Time> {
+        (n > 0)
+            .then(|| self.solves.windows(n).map(average).min())
+            .flatten()
+    }
+}
+
+fn average(solves: &[&Solve]) -> Time {
+    let mut results = solves
+        .iter()
+        .map(|solve| solve.result())
+        .collect::<Vec<_>>();
+    results.sort();
+
+    let trimmed = match results.len() {
+        0..=2 => 0,
+        n => (n * 5 / 100).max(1),
+    };
+
+    let mut total = Duration::ZERO;
+    for result in &results[trimmed..results.len() - trimmed] {
+        match result {
+            Time::Finished(time) => total += *time,
+            Time::Dnf => return Time::Dnf,
+        }
+    }
+
+    Time::Finished(total / (results.len() - 2 * trimmed) as u32)
+}
+
+#[test]
+fn statistics_work() {
+    use crate::{BoxPuzzle, Direction::*};
+
+    let scramble = BoxPuzzle::<u8>::default();
+    let solve = |millis, dnf| Solve {
+        dnf,
+        ..Solve::new(
+            &scramble,
+            vec![
+                Slide::new(Up, 2),
+                Slide::new(Up, 1),
+                Slide::new(Left, 1),
+                Slide::new(Down, 1),
+            ],
+            Duration::from_millis(millis),
+        )
+    };
+
+    // separate slides the same way are separate moves
+    assert_eq!(solve(2000, false).stm(), 5);
+    assert_eq!(solve(2000, false).mtm(), 4);
+    assert_eq!(solve(2000, false).tps(), Some(2.5));
+    assert_eq!(solve(0, false).tps(), None);
+    assert_eq!(
+        solve(2000, false).scramble,
+        (0..16).cycle().skip(1).take(16).collect::<Vec<_>>()
+    );
+
+    let mut session = Session::new();
+    for (millis, dnf) in [
+        (10_000, false),
+        (12_000, false),
+        (8_000, true),
+        (11_000, false),
+    ] {
+        session.push(solve(millis, dnf));
+    }
+
+    let stats = session.stats((4, 4));
+    assert_eq!(stats.average_of(5), None);
+    assert_eq!(stats.best(), Some(Time::Finished(Duration::from_secs(10))));
+    assert_eq!(stats.worst(), Some(Time::Dnf));
+    assert_eq!(stats.mean(), Some(Duration::from_secs(11)));
+
+    // one DNF is trimmed away, two are not
+    session.push(solve(9_000, false));
+    let stats = session.stats((4, 4));
+    assert_eq!(
+        stats.average_of(5),
+        Some(Time::Finished(Duration::from_secs(11)))
+    );
+    session.push(solve(9_000, true));
+    let stats = session.stats((4, 4));
+    assert_eq!(stats.average_of(5), Some(Time::Dnf));
+    assert_eq!(
+        stats.best_average_of(5),
+        Some(Time::Finished(Duration::from_secs(11)))
+    );
+    assert_eq!(stats.average_of(12), None);
+
+    // sizes are kept apart
+    session.push(Solve::new(
+        &BoxPuzzle::<u8>::random((3, 3)),
+        vec![],
+        Duration::from_secs(3),
+    ));
+    assert_eq!(
+        session.personal_bests().collect::<Vec<_>>(),
+        [
+            ((3, 3), Time::Finished(Duration::from_secs(3))),
+            ((4, 4), Time::Finished(Duration::from_secs(9)))
+        ]
+    );
+    assert_eq!(session.stats((4, 4)).solves().len(), 6);
+
+    let path = std::env::temp_dir().join(format!("jugo-session-{}.json", std::process::id()));
+    assert_eq!(Session::load(&path).unwrap(), Session::new());
+    session.save(&path).unwrap();
+    assert_eq!(Session::load(&path).unwrap(), session);
+    fs::remove_file(path).unwrap();
+
+    assert_eq!(
+        Time::Finished(Duration::from_millis(61_005)).to_string(),
+        "1:01.005"
+    );
+    assert_eq!(Time::Dnf.to_string(), "DNF");
+}
@@ -0,0 +1,428 @@
+use std::collections::HashSet;
+
+use crate::{is_solvable, Direction, Piece, Puzzle, TorusPuzzle};
+
+/// An optimal solver based on IDA* with the Manhattan distance heuristic, measured round the
+/// torus for [`TorusPuzzle`]s.
+///
+/// Solutions are sequences of single-tile moves, each being the [`Direction`] a tile slides
+/// in, i.e. what [`Puzzle::slide_towards`] takes with a distance of `1`.
+///
+/// Every piece goes back to where its number belongs, which ignores the colour classes of a
+/// [`TilePuzzle`](crate::TilePuzzle).
+#[derive(Debug, Clone)]
+pub struct Solver {
+    start: Board,
+}
+
+impl Solver {
+    pub fn new<T: Piece, P: Puzzle<T>>(puzzle: &P) -> Self {
+        let (width, height) = puzzle.shape();
+        let cells = puzzle
+            .iter()
+            .cloned()
+            .map(num::cast)
+            .collect::<Option<_>>()
+            .expect("could not cast pieces to usize");
+
+        Self {
+            start: Board::new(width, height, cells, false),
+        }
+    }
+
+    /// Solves `puzzle` as if it were a [`TorusPuzzle`], with moves wrapping around the edges.
+    pub fn new_torus<T: Piece, P: Puzzle<T>>(puzzle: &P) -> Self {
+        let (width, height) = puzzle.shape();
+        let cells = puzzle
+            .iter()
+            .cloned()
+            .map(num::cast)
+            .collect::<Option<_>>()
+            .expect("could not cast pieces to usize");
+
+        Self {
+            start: Board::new(width, height, cells, true),
+        }
+    }
+
+    pub fn is_solvable(&self) -> bool {
+        let Board {
+            width,
+            height,
+            ref cells,
+            wrap,
+            ..
+        } = self.start;
+
+        match wrap {
+            false => is_solvable(cells, width),
+            true => TorusPuzzle::from_pieces((width, height), cells.clone())
+                .is_ok_and(|torus| torus.is_solvable()),
+        }
+    }
+
+    /// Returns one of the shortest solutions, or `None` if the puzzle is unsolvable.
+    pub fn solve(&self) -> Option<Vec<Direction>> {
+        self.solve_weighted(1)
+    }
+
+    /// Returns a solution at most `weight` times as long as the shortest ones, or `None` if the
+    /// puzzle is unsolvable.
+    ///
+    /// This weighs the heuristic by `weight`, which gets much faster than [`solve`](Self::solve)
+    /// on boards where optimal solving is out of reach.
+    pub fn solve_weighted(&self, weight: usize) -> Option<Vec<Direction>> {
+        assert!(weight > 0, "weight must be at least 1");
+        if !self.is_solvable() {
+            return None;
+        }
+
+        let mut board = self.start.clone();
+        let mut path = Vec::new();
+        let mut bound = weight * board.h;
+
+        loop {
+            match search(&mut board, &mut path, bound, weight) {
+                Ok(()) => return Some(path),
+                Err(next_bound) => bound = next_bound,
+            }
+        }
+    }
+
+    /// Length of the shortest solutions, or `None` if the puzzle is unsolvable.
+    pub fn optimal_length(&self) -> Option<usize> {
+        self.solve().map(|solution| solution.len())
+    }
+
+    /// Enumerates every shortest solution, each exactly once.
+    ///
+    /// Positions reached again at the same depth through a transposed move order are only
+    /// searched once: subtrees known not to lead to the goal are remembered and skipped.
+    pub fn solutions(&self) -> Solutions {
+        let depth = self.optimal_length();
+
+        Solutions {
+            board: self.start.clone(),
+            depth: depth.unwrap_or(0),
+            path: Vec::new(),
+            stack: match depth {
+                Some(depth) if depth > 0 => vec![Frame::default()],
+                _ => Vec::new(),
+            },
+            dead_ends: HashSet::new(),
+            trivial: depth == Some(0),
+        }
+    }
+}
+
+/// Iterator over every shortest solution of a position, returned by [`Solver::solutions`].
+#[derive(Debug)]
+pub struct Solutions {
+    board: Board,
+    depth: usize,
+    path: Vec<Direction>,
+    stack: Vec<Frame>,
+    dead_ends: HashSet<(Vec<usize>, usize)>,
+    // already solved: the only solution is the empty one
+    trivial: bool,
+}
+
+#[derive(Debug, Default)]
+struct Frame {
+    next: usize,
+    found: bool,
+}
+
+impl Iterator for Solutions {
+    type Item = Vec<Direction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.trivial {
+            self.trivial = false;
+            return Some(Vec::new());
+        }
+
+        loop {
+            let frame = self.stack.last_mut()?;
+
+            // every move from here has been tried; backtrack
+            if frame.next == Direction::ALL.len() {
+                let found = frame.found;
+                self.stack.pop();
+
+                if !found {
+                    let key = (self.board.cells.clone(), self.path.len());
+                    self.dead_ends.insert(key);
+                }
+
+                if let Some(direction) = self.path.pop() {
+                    self.board.undo(direction);
+                    if let Some(parent) = self.stack.last_mut() {
+                        parent.found |= found;
+                    }
+                }
+
+                continue;
+            }
+
+            let direction = Direction::ALL[frame.next];
+            frame.next += 1;
+
+            // going straight back can never be part of a shortest solution
+            if self.path.last() == Some(&direction.opposite()) || !self.board.step(direction) {
+                continue;
+            }
+
+            let depth = self.path.len() + 1;
+            if depth + self.board.h > self.depth
+                || self.dead_ends.contains(&(self.board.cells.clone(), depth))
+            {
+                self.board.undo(direction);
+                continue;
+            }
+
+            self.path.push(direction);
+
+            if self.board.h == 0 {
+                let solution = self.path.clone();
+
+                self.path.pop();
+                self.board.undo(direction);
+                frame.found = true;
+
+                return Some(solution);
+            }
+
+            self.stack.push(Frame::default());
+        }
+    }
+}
+
+fn search(
+    board: &mut Board,
+    path: &mut Vec<Direction>,
+    bound: usize,
+    weight: usize,
+) -> Result<(), usize> {
+    let f = path.len() + weight * board.h;
+    if f > bound {
+        return Err(f);
+    }
+    if board.h == 0 {
+        return Ok(());
+    }
+
+    let mut next_bound = usize::MAX;
+    for direction in Direction::ALL {
+        if path.last() == Some(&direction.opposite()) || !board.step(direction) {
+            continue;
+        }
+
+        path.push(direction);
+        match search(board, path, bound, weight) {
+            Ok(()) => return Ok(()),
+            Err(f) => next_bound = next_bound.min(f),
+        }
+        path.pop();
+
+        board.undo(direction);
+    }
+
+    Err(next_bound)
+}
+
+#[derive(Debug, Clone)]
+struct Board {
+    width: usize,
+    height: usize,
+    cells: Vec<usize>,
+    blank: usize,
+    // whether moves wrap around the edges, as on a torus
+    wrap: bool,
+    // sum of the Manhattan distances of every piece to where it belongs
+    h: usize,
+}
+
+impl Board {
+    fn new(width: usize, height: usize, cells: Vec<usize>, wrap: bool) -> Self {
+        let blank = cells
+            .iter()
+            .position(|&piece| piece == 0)
+            .expect("potential BUG: could not find an empty piece");
+
+        let mut board = Self {
+            width,
+            height,
+            cells,
+            blank,
+            wrap,
+            h: 0,
+        };
+        board.h = (0..board.cells.len())
+            .map(|idx| board.distance(idx, board.cells[idx]))
+            .sum();
+
+        board
+    }
+
+    fn distance(&self, idx: usize, piece: usize) -> usize {
+        if piece == 0 {
+            return 0;
+        }
+
+        let goal = piece - 1;
+        let dx = (idx % self.width).abs_diff(goal % self.width);
+        let dy = (idx / self.width).abs_diff(goal / self.width);
+        match self.wrap {
+            false => dx + dy,
+            true => dx.min(self.width - dx) + dy.min(self.height - dy),
+        }
+    }
+
+    // slides the piece next to the blank towards `direction`, if there is one
+    fn step(&mut self, direction: Direction) -> bool {
+        let (dx, dy): (isize, isize) = direction.into();
+        let (x, y) = (self.blank % self.width, self.blank / self.width);
+
+        let from = match (x.checked_add_signed(-dx), y.checked_add_signed(-dy)) {
+            _ if self.wrap => {
+                let x = (x as isize - dx).rem_euclid(self.width as isize) as usize;
+                let y = (y as isize - dy).rem_euclid(self.height as isize) as usize;
+                y * self.width + x
+            }
+            (Some(x), Some(y)) if x < self.width && y < self.height => y * self.width + x,
+            _ => return false,
+        };
+        // a row or column of one wraps onto the blank itself
+        if from == self.blank {
+            return false;
+        }
+
+        let piece = self.cells[from];
+        self.h = self.h + self.distance(self.blank, piece) - self.distance(from, piece);
+        self.cells.swap(from, self.blank);
+        self.blank = from;
+
+        true
+    }
+
+    fn undo(&mut self, direction: Direction) {
+        let stepped = self.step(direction.opposite());
+        debug_assert!(stepped);
+    }
+}
+
+#[cfg(test)]
+fn shortest_path_counts(width: usize, height: usize) -> Vec<(Vec<usize>, usize, usize)> {
+    use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+    // moves are reversible, so counting from the goal is the same as counting towards it
+    let goal = Board::new(
+        width,
+        height,
+        (1..width * height).chain([0]).collect(),
+        false,
+    );
+    let mut counts = HashMap::from([(goal.cells.clone(), (0, 1))]);
+    let mut queue = VecDeque::from([goal]);
+
+    while let Some(board) = queue.pop_front() {
+        let (distance, count) = counts[&board.cells];
+
+        for direction in Direction::ALL {
+            let mut next = board.clone();
+            if !next.step(direction) {
+                continue;
+            }
+
+            match counts.entry(next.cells.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert((distance + 1, count));
+                    queue.push_back(next);
+                }
+                Entry::Occupied(mut entry) if entry.get().0 == distance + 1 => {
+                    entry.get_mut().1 += count;
+                }
+                Entry::Occupied(_) => {}
+            }
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|(cells, (distance, count))| (cells, distance, count))
+        .collect()
+}
+
+#[test]
+fn solutions_match_bfs() {
+    for (width, height) in [(2, 2), (3, 2), (2, 3)] {
+        for (cells, distance, count) in shortest_path_counts(width, height) {
+            let solver = Solver {
+                start: Board::new(width, height, cells, false),
+            };
+            let solutions = solver.solutions().collect::<Vec<_>>();
+
+            assert_eq!(solutions.len(), count, "{:?}", solver.start.cells);
+            assert_eq!(
+                solutions.iter().collect::<HashSet<_>>().len(),
+                count,
+                "{:?}",
+                solver.start.cells
+            );
+
+            for solution in solutions {
+                assert_eq!(solution.len(), distance);
+
+                let mut board = solver.start.clone();
+                for direction in solution {
+                    assert!(board.step(direction));
+                }
+                assert_eq!(board.h, 0);
+            }
+        }
+    }
+}
+
+#[test]
+fn solve_works() {
+    use crate::StackPuzzle;
+    use rand::{Rng, SeedableRng};
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    let mut rng = Xoshiro256StarStar::seed_from_u64(0x6a75676f);
+    for _ in 0..8 {
+        let mut puzzle = StackPuzzle::default();
+        for _ in 0..24 {
+            puzzle.slide_towards(Direction::ALL[rng.gen_range(0..4)], 1);
+        }
+
+        let solver = Solver::new(&puzzle);
+        let solution = solver.solve().unwrap();
+        assert!(solution.len() <= 24);
+
+        let weighted = solver.solve_weighted(3).unwrap();
+        assert!(weighted.len() <= 3 * solution.len());
+        let mut board = solver.start.clone();
+        for &direction in &weighted {
+            assert!(board.step(direction));
+        }
+        assert_eq!(board.h, 0);
+
+        for direction in solution {
+            assert_eq!(puzzle.slide_towards(direction, 1), Some(1));
+        }
+        assert_eq!(Solver::new(&puzzle).optimal_length(), Some(0));
+    }
+}
+
+#[test]
+fn unsolvable_has_no_solutions() {
+    let solver = Solver {
+        start: Board::new(3, 2, vec![2, 1, 3, 4, 5, 0], false),
+    };
+
+    assert!(!solver.is_solvable());
+    assert_eq!(solver.solve(), None);
+    assert_eq!(solver.solutions().count(), 0);
+}
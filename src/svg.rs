@@ -0,0 +1,192 @@
+use core::fmt::Write;
+
+use crate::{Direction, Palette, Piece, Puzzle, Scheme};
+
+/// Renders puzzles as standalone SVG documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Svg {
+    /// Side of a tile, in pixels.
+    pub tile_size: u32,
+    /// Space between tiles and around the board, in pixels.
+    pub gap: u32,
+    /// Corner radius of a tile, in pixels.
+    pub radius: u32,
+    pub font_family: String,
+    /// Font size of the numbers on tiles, in pixels.
+    pub font_size: u32,
+    pub scheme: Scheme,
+    pub palette: Palette,
+    /// How many frames go in a row of a strip before wrapping.
+    pub strip_columns: usize,
+}
+
+impl Default for Svg {
+    fn default() -> Self {
+        Self {
+            tile_size: 48,
+            gap: 4,
+            radius: 6,
+            font_family: "sans-serif".to_owned(),
+            font_size: 22,
+            scheme: Scheme::default(),
+            palette: Palette::default(),
+            strip_columns: 8,
+        }
+    }
+}
+
+impl Svg {
+    /// Renders a single board.
+    pub fn render<T: Piece, P: Puzzle<T>>(&self, puzzle: &P) -> String {
+        let (width, height) = self.board_size(puzzle.shape());
+
+        let mut svg = String::new();
+        self.open(&mut svg, width, height);
+        self.board(&mut svg, puzzle, (0, 0));
+        svg + "</svg>\n"
+    }
+
+    /// Renders `puzzle` and every state after each of `moves`, in the sense of
+    /// [`Puzzle::slide_towards`], as numbered frames. Returns `None` if a move cannot be made.
+    pub fn render_strip<T, P>(&self, puzzle: &P, moves: &[Direction]) -> Option<String>
+    where
+        T: Piece,
+        P: Puzzle<T> + Clone,
+    {
+        let (board_width, board_height) = self.board_size(puzzle.shape());
+        // room for the number above every frame
+        let label = self.font_size + self.gap;
+        let frames = moves.len() + 1;
+        let columns = self.strip_columns.clamp(1, frames);
+        let rows = frames.div_ceil(columns);
+
+        let mut svg = String::new();
+        self.open(
+            &mut svg,
+            columns as u32 * (board_width + self.gap) - self.gap,
+            rows as u32 * (label + board_height + self.gap) - self.gap,
+        );
+
+        let mut puzzle = puzzle.clone();
+        for frame in 0..frames {
+            if frame > 0 && puzzle.slide_towards(moves[frame - 1], 1) != Some(1) {
+                return None;
+            }
+
+            let x = (frame % columns) as u32 * (board_width + self.gap);
+            let y = (frame / columns) as u32 * (label + board_height + self.gap);
+            let caption = match frame {
+                0 => "0".to_owned(),
+                frame => format!("{frame} {}", moves[frame - 1].arrow()),
+            };
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="middle" fill="{}">{caption}</text>"#,
+                x + board_width / 2,
+                y + self.font_size,
+                self.palette.background,
+            );
+            self.board(&mut svg, &puzzle, (x, y + label));
+        }
+
+        Some(svg + "</svg>\n")
+    }
+
+    fn board_size(&self, (width, height): (usize, usize)) -> (u32, u32) {
+        let size = |tiles: usize| tiles as u32 * (self.tile_size + self.gap) + self.gap;
+        (size(width), size(height))
+    }
+
+    fn open(&self, svg: &mut String, width: u32, height: u32) {
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="{}" font-size="{}">"#,
+            escape(&self.font_family),
+            self.font_size,
+        );
+    }
+
+    fn board<T: Piece, P: Puzzle<T>>(&self, svg: &mut String, puzzle: &P, (left, top): (u32, u32)) {
+        let shape = puzzle.shape();
+        let (width, height) = self.board_size(shape);
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{left}" y="{top}" width="{width}" height="{height}" rx="{}" fill="{}"/>"#,
+            self.radius, self.palette.background,
+        );
+
+        for ((x, y), piece) in puzzle.iter_indexed() {
+            let piece: usize = num::cast(piece.clone()).expect("could not cast piece to usize");
+            if piece == 0 {
+                continue;
+            }
+
+            let goal = ((piece - 1) % shape.0, (piece - 1) / shape.0);
+            let fill = self.palette.tile(self.scheme.class(goal, shape));
+            let x = left + self.gap + x as u32 * (self.tile_size + self.gap);
+            let y = top + self.gap + y as u32 * (self.tile_size + self.gap);
+            let half = self.tile_size / 2;
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" rx="{}" fill="{fill}"/><text x="{}" y="{}" text-anchor="middle" dominant-baseline="central" fill="{}">{piece}</text>"#,
+                self.radius,
+                x + half,
+                y + half,
+                self.palette.text,
+                size = self.tile_size,
+            );
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[test]
+fn svg_works() {
+    use crate::{BoxPuzzle, Direction::*, Rgb};
+
+    let svg = Svg {
+        font_family: "\"Fira Sans\", sans-serif".to_owned(),
+        scheme: Scheme::Rows,
+        ..Svg::default()
+    };
+
+    let board = svg.render(&BoxPuzzle::<u8>::default());
+    assert!(
+        board.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="212" height="212""#)
+    );
+    assert!(board.contains("font-family=\"&quot;Fira Sans&quot;, sans-serif\""));
+    assert!(board.ends_with("</svg>\n"));
+    // the board and 15 tiles, coloured by row
+    assert_eq!(board.matches("<rect").count(), 16);
+    assert_eq!(board.matches("<text").count(), 15);
+    assert_eq!(
+        board
+            .matches(&format!("fill=\"{}\"", Rgb(0x61, 0xaf, 0xef)))
+            .count(),
+        4
+    );
+    assert!(board.contains(">15</text>"));
+
+    let strip = svg
+        .render_strip(&BoxPuzzle::<u8>::default(), &[Down, Right, Up])
+        .unwrap();
+    assert_eq!(strip.matches("<rect").count(), 4 * 16);
+    assert!(strip.contains(">0</text>"));
+    assert!(strip.contains(">3 ↑</text>"));
+
+    let strip = Svg {
+        strip_columns: 2,
+        ..svg.clone()
+    }
+    .render_strip(&BoxPuzzle::<u8>::default(), &[Down, Right, Up])
+    .unwrap();
+    assert!(strip.contains(r#"width="428" height="480""#));
+
+    assert_eq!(svg.render_strip(&BoxPuzzle::<u8>::default(), &[Up]), None);
+}
@@ -0,0 +1,196 @@
+use crate::{BoxPuzzle, Direction, Piece, Puzzle};
+
+/// A symmetry of the board, for looking at a puzzle from another side.
+///
+/// [`apply`](Self::apply) relabels the tiles so that the goal still maps to the goal. Only
+/// [`Transpose`](Self::Transpose) keeps the empty piece's home corner in place; the others send
+/// it to another corner, so the tiles are numbered as if the empty piece were then walked back
+/// home along the edges. [`solution`](Self::solution) maps a solution of the transformed board
+/// back to one of the original, which is longer by that walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    /// Swaps rows and columns, so that a `width`x`height` board becomes `height`x`width`.
+    Transpose,
+    /// Swaps left and right.
+    MirrorHorizontal,
+    /// Swaps top and bottom.
+    MirrorVertical,
+    Rotate180,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 4] = [
+        Symmetry::Transpose,
+        Symmetry::MirrorHorizontal,
+        Symmetry::MirrorVertical,
+        Symmetry::Rotate180,
+    ];
+
+    /// The shape a board of `shape` becomes.
+    pub fn shape(self, (width, height): (usize, usize)) -> (usize, usize) {
+        match self {
+            Symmetry::Transpose => (height, width),
+            _ => (width, height),
+        }
+    }
+
+    /// Where the cell at `(x, y)` of a board of `shape` goes.
+    pub fn position(
+        self,
+        (x, y): (usize, usize),
+        (width, height): (usize, usize),
+    ) -> (usize, usize) {
+        match self {
+            Symmetry::Transpose => (y, x),
+            Symmetry::MirrorHorizontal => (width - 1 - x, y),
+            Symmetry::MirrorVertical => (x, height - 1 - y),
+            Symmetry::Rotate180 => (width - 1 - x, height - 1 - y),
+        }
+    }
+
+    /// Where a tile moving towards `direction` moves once transformed. Every symmetry is its own
+    /// inverse, so this maps directions back as well.
+    pub fn direction(self, direction: Direction) -> Direction {
+        use Direction::*;
+        match (self, direction) {
+            (Symmetry::Transpose, Up) => Left,
+            (Symmetry::Transpose, Down) => Right,
+            (Symmetry::Transpose, Left) => Up,
+            (Symmetry::Transpose, Right) => Down,
+            (Symmetry::MirrorHorizontal | Symmetry::Rotate180, Left) => Right,
+            (Symmetry::MirrorHorizontal | Symmetry::Rotate180, Right) => Left,
+            (Symmetry::MirrorVertical | Symmetry::Rotate180, Up) => Down,
+            (Symmetry::MirrorVertical | Symmetry::Rotate180, Down) => Up,
+            (_, direction) => direction,
+        }
+    }
+
+    /// [`direction`](Self::direction) for every move.
+    pub fn moves(self, moves: &[Direction]) -> Vec<Direction> {
+        moves
+            .iter()
+            .map(|&direction| self.direction(direction))
+            .collect()
+    }
+
+    /// The transformed board, whose moves are those of `puzzle` mapped by
+    /// [`direction`](Self::direction).
+    pub fn apply<T: Piece, P: Puzzle<T>>(self, puzzle: &P) -> BoxPuzzle<T> {
+        let shape = puzzle.shape();
+        let (width, height) = self.shape(shape);
+        let (home, _) = self.walk_home(shape);
+
+        let mut pieces = vec![T::zero(); width * height];
+        for (position, piece) in puzzle.iter_indexed() {
+            let (x, y) = self.position(position, shape);
+            pieces[y * width + x] = match num::cast(piece.clone()) {
+                Some(0) => T::zero(),
+                Some(piece) => {
+                    let goal = ((piece - 1) % shape.0, (piece - 1) / shape.0);
+                    let (x, y) = self.position(goal, shape);
+                    num::cast(home[y * width + x] + 1).expect("could not cast pieces to usize")
+                }
+                None => panic!("could not cast pieces to usize"),
+            };
+        }
+
+        BoxPuzzle::from_pieces((width, height), pieces)
+            .expect("potential BUG: transformed pieces are not a board")
+    }
+
+    /// Maps a solution of the board [`apply`](Self::apply) made back to a solution of the
+    /// original, which is a board of `shape`.
+    pub fn solution(self, shape: (usize, usize), solution: &[Direction]) -> Vec<Direction> {
+        let (_, walk) = self.walk_home(shape);
+        let mut moves = self.moves(solution);
+
+        // the original is left with its empty piece where the transformed one started its walk
+        for direction in walk.iter().rev().map(|&d| self.direction(d).opposite()) {
+            match moves.last() {
+                Some(&last) if last == direction.opposite() => {
+                    moves.pop();
+                }
+                _ => moves.push(direction),
+            }
+        }
+
+        moves
+    }
+
+    // walks the empty piece of the transformed board from where the bottom-right corner went
+    // back there, along the row and then the column: where each cell's tile ends up, and the
+    // moves taken
+    fn walk_home(self, shape: (usize, usize)) -> (Vec<usize>, Vec<Direction>) {
+        let (width, height) = self.shape(shape);
+        let (mut x, mut y) = self.position((shape.0 - 1, shape.1 - 1), shape);
+
+        let mut cells = (0..width * height).collect::<Vec<_>>();
+        let mut walk = vec![];
+        while (x, y) != (width - 1, height - 1) {
+            let (next, direction) = match x + 1 < width {
+                true => ((x + 1, y), Direction::Left),
+                false => ((x, y + 1), Direction::Up),
+            };
+            cells.swap(y * width + x, next.1 * width + next.0);
+            walk.push(direction);
+            (x, y) = next;
+        }
+
+        let mut home = vec![0; width * height];
+        for (idx, &cell) in cells.iter().enumerate() {
+            home[cell] = idx;
+        }
+
+        (home, walk)
+    }
+}
+
+#[test]
+fn symmetry_works() {
+    use crate::{is_solvable, Direction::*, Solver};
+
+    // transposing keeps the empty piece home, and so the goal as it is
+    let solved = BoxPuzzle::<u8>::from_pieces((3, 3), vec![1, 2, 3, 4, 5, 6, 7, 8, 0]).unwrap();
+    assert_eq!(
+        Symmetry::Transpose.apply(&solved),
+        BoxPuzzle::from_pieces((3, 3), vec![1, 2, 3, 4, 5, 6, 7, 8, 0]).unwrap()
+    );
+    // 1 2 3 4      1 2 3 4
+    // 5 6 7    ->    5 6 7
+    let solved = BoxPuzzle::<u8>::from_pieces((4, 2), vec![1, 2, 3, 4, 5, 6, 7, 0]).unwrap();
+    let mut mirrored = Symmetry::MirrorHorizontal.apply(&solved);
+    assert_eq!(
+        mirrored,
+        BoxPuzzle::from_pieces((4, 2), vec![1, 2, 3, 4, 0, 5, 6, 7]).unwrap()
+    );
+    assert_eq!(mirrored.slide_towards(Left, 3), Some(3));
+    assert!(mirrored.is_solved());
+
+    let mut rng = rand::thread_rng();
+    for shape in [(3, 3), (2, 4), (4, 2), (1, 4)] {
+        let puzzle = BoxPuzzle::<u8>::random_with_rng(&mut rng, shape);
+        for symmetry in Symmetry::ALL {
+            let transformed = symmetry.apply(&puzzle);
+            let pieces = transformed.iter().copied().collect::<Vec<_>>();
+            assert!(is_solvable(&pieces, transformed.shape().0), "{symmetry:?}");
+
+            // moves commute with the transform
+            let moves = [Up, Left, Down, Right, Up];
+            let (mut moved, mut transformed_moved) = (puzzle.clone(), transformed.clone());
+            for (&direction, &mapped) in moves.iter().zip(symmetry.moves(&moves).iter()) {
+                assert_eq!(
+                    moved.slide_towards(direction, 1),
+                    transformed_moved.slide_towards(mapped, 1)
+                );
+            }
+            assert_eq!(symmetry.apply(&moved), transformed_moved, "{symmetry:?}");
+
+            let solution = Solver::new(&transformed).solve().unwrap();
+            let mut original = puzzle.clone();
+            for direction in symmetry.solution(shape, &solution) {
+                assert_eq!(original.slide_towards(direction, 1), Some(1));
+            }
+            assert!(original.is_solved(), "{symmetry:?} of {puzzle:?}");
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! A conformance suite for [`Puzzle`] implementations.
+//!
+//! Every check builds the puzzle under test from a [`BoxPuzzle`], which then serves as the
+//! reference model, so a third-party implementation only has to provide a conversion:
+//!
+//! ```ignore
+//! jugo::testing::run((4, 4), |reference: BoxPuzzle<u8>| MyPuzzle::from(reference));
+//! ```
+
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256StarStar;
+
+use core::fmt::Debug;
+
+use crate::{
+    is_solvable, BoxPuzzle, Direction, Piece, Player, Position, Puzzle, Recordable, Recorder,
+};
+
+const SEED: u64 = 0x6a75676f;
+const BOARDS: usize = 32;
+const SLIDES: usize = 64;
+
+/// Runs every check of this module against puzzles of the given shape.
+pub fn run<P, T>(shape: (usize, usize), mut make: impl FnMut(BoxPuzzle<T>) -> P)
+where
+    P: Puzzle<T> + Clone + Debug,
+    T: Piece + Debug,
+{
+    let mut rng = Xoshiro256StarStar::seed_from_u64(SEED);
+
+    for _ in 0..BOARDS {
+        let reference = BoxPuzzle::random_with_rng(&mut rng, shape);
+        let puzzle = make(reference.clone());
+
+        assert_eq!(puzzle.shape(), shape, "shape() differs from the reference");
+        check_indexing(&puzzle);
+        check_out_of_bounds(puzzle, &reference);
+
+        let puzzle = make(reference.clone());
+        check_directions(puzzle, &reference);
+
+        let puzzle = make(reference.clone());
+        check_parity(puzzle, &mut rng);
+
+        let puzzle = make(reference.clone());
+        check_against_reference(puzzle, reference, &mut rng);
+    }
+}
+
+/// Checks that indexing, [`Puzzle::get`], [`Puzzle::index_of`] and the iterators all agree on
+/// where every piece is.
+pub fn check_indexing<P, T>(puzzle: &P)
+where
+    P: Puzzle<T> + Debug,
+    T: Piece + Debug,
+{
+    let (width, height) = puzzle.shape();
+    assert_eq!(puzzle.iter().count(), width * height, "{puzzle:?}");
+
+    for ((x, y), piece) in puzzle.iter_indexed() {
+        assert_eq!(puzzle.index_of(piece.clone()), Some((x, y)), "{puzzle:?}");
+        assert_eq!(&puzzle[(x, y)], piece, "{puzzle:?}");
+        assert_eq!(&puzzle[Position::new(x, y)], piece, "{puzzle:?}");
+        assert_eq!(puzzle.get((x, y)), Some(piece), "{puzzle:?}");
+        assert_eq!(puzzle.row(y).nth(x), Some(piece), "{puzzle:?}");
+        assert_eq!(puzzle.column(x).nth(y), Some(piece), "{puzzle:?}");
+    }
+
+    assert_eq!(puzzle.get((width, 0)), None, "{puzzle:?}");
+    assert_eq!(puzzle.get((0, height)), None, "{puzzle:?}");
+}
+
+/// Checks that slides which cannot happen leave the puzzle untouched: out of bounds, not in
+/// line with the empty piece, or past an edge.
+pub fn check_out_of_bounds<P, T>(mut puzzle: P, reference: &BoxPuzzle<T>)
+where
+    P: Puzzle<T> + Debug,
+    T: Piece + Debug,
+{
+    let (width, height) = puzzle.shape();
+    let empty = empty_of(&puzzle);
+
+    assert_eq!(puzzle.slide_from((width, empty.1)), None, "{puzzle:?}");
+    assert_eq!(puzzle.slide_from((empty.0, height)), None, "{puzzle:?}");
+    assert_eq!(
+        puzzle.slide_from((usize::MAX, usize::MAX)),
+        None,
+        "{puzzle:?}"
+    );
+    assert_eq!(puzzle.slide_from(empty), Some(0), "{puzzle:?}");
+
+    for x in (0..width).filter(|&x| x != empty.0) {
+        for y in (0..height).filter(|&y| y != empty.1) {
+            assert_eq!(puzzle.slide_from((x, y)), None, "{puzzle:?}");
+        }
+    }
+
+    for direction in Direction::ALL {
+        let (dx, dy): (isize, isize) = direction.into();
+        // how many pieces there are behind the empty piece, seen from `direction`
+        let available = match (dx, dy) {
+            (1, _) => empty.0,
+            (-1, _) => width - 1 - empty.0,
+            (_, 1) => empty.1,
+            _ => height - 1 - empty.1,
+        };
+
+        let moved = puzzle.slide_towards(direction, available + 1);
+        assert_eq!(moved, None, "{direction:?}: {puzzle:?}");
+    }
+
+    assert_same(&puzzle, reference);
+}
+
+/// Checks [`Puzzle::slide_towards`] in all four directions and over every possible distance:
+/// the pieces between the empty piece and the slid one all move by one cell in `direction`.
+pub fn check_directions<P, T>(puzzle: P, reference: &BoxPuzzle<T>)
+where
+    P: Puzzle<T> + Clone + Debug,
+    T: Piece + Debug,
+{
+    let (width, height) = puzzle.shape();
+    let empty = empty_of(&puzzle);
+
+    for direction in Direction::ALL {
+        let (dx, dy): (isize, isize) = direction.into();
+
+        for distance in 1.. {
+            let from = (
+                empty.0.checked_add_signed(-dx * distance as isize),
+                empty.1.checked_add_signed(-dy * distance as isize),
+            );
+            let from = match from {
+                (Some(x), Some(y)) if x < width && y < height => (x, y),
+                _ => break,
+            };
+
+            let mut slid = puzzle.clone();
+            assert_eq!(
+                slid.slide_towards(direction, distance),
+                Some(distance),
+                "{direction:?} by {distance}: {puzzle:?}"
+            );
+            assert_eq!(
+                empty_of(&slid),
+                from,
+                "{direction:?} by {distance}: {slid:?}"
+            );
+
+            for step in 0..distance as isize {
+                let before = (
+                    (from.0 as isize + dx * step) as usize,
+                    (from.1 as isize + dy * step) as usize,
+                );
+                let after = (
+                    (before.0 as isize + dx) as usize,
+                    (before.1 as isize + dy) as usize,
+                );
+                assert_eq!(
+                    slid[after], reference[before],
+                    "{direction:?} by {distance}: {slid:?}"
+                );
+            }
+        }
+    }
+}
+
+/// Checks that random slides never change whether the puzzle is solvable.
+pub fn check_parity<P, T>(mut puzzle: P, rng: &mut impl Rng)
+where
+    P: Puzzle<T> + Debug,
+    T: Piece + Debug,
+{
+    let (width, height) = puzzle.shape();
+    let solvable = solvability_of(&puzzle);
+
+    for _ in 0..SLIDES {
+        puzzle.slide_from((rng.gen_range(0..width), rng.gen_range(0..height)));
+        assert_eq!(solvability_of(&puzzle), solvable, "{puzzle:?}");
+    }
+}
+
+/// Checks that random slides give the same results as on the [`BoxPuzzle`] reference model.
+pub fn check_against_reference<P, T>(puzzle: P, reference: BoxPuzzle<T>, rng: &mut impl Rng)
+where
+    P: Puzzle<T> + Debug,
+    T: Piece + Debug,
+{
+    check_against_model(puzzle, reference, rng);
+}
+
+/// Like [`check_against_reference`] with any `model`, for puzzles built on another one, like
+/// [`Recorder`](crate::Recorder) or [`Zobrist`](crate::Zobrist), which have to slide just like
+/// the one they wrap.
+pub fn check_against_model<P, M, T>(mut puzzle: P, mut model: M, rng: &mut impl Rng)
+where
+    P: Puzzle<T> + Debug,
+    M: Puzzle<T> + Debug,
+    T: Piece + Debug,
+{
+    let (width, height) = puzzle.shape();
+
+    for _ in 0..SLIDES {
+        assert!(
+            puzzle.legal_moves().eq(model.legal_moves()),
+            "legal_moves(): {puzzle:?}"
+        );
+
+        // a little out of bounds sometimes
+        let from = (rng.gen_range(0..=width), rng.gen_range(0..=height));
+        assert_eq!(
+            puzzle.slide_from(from),
+            model.slide_from(from),
+            "slide_from({from:?}): {puzzle:?}"
+        );
+
+        let direction = Direction::ALL[rng.gen_range(0..4)];
+        let distance = rng.gen_range(0..=width.max(height));
+        assert_eq!(
+            puzzle.slide_towards(direction, distance),
+            model.slide_towards(direction, distance),
+            "slide_towards({direction:?}, {distance}): {puzzle:?}"
+        );
+
+        assert_same(&puzzle, &model);
+        assert_eq!(puzzle.is_solved(), model.is_solved(), "{puzzle:?}");
+    }
+}
+
+/// Checks that random slides recorded by a [`Recorder`] play back move by move with a
+/// [`Player`], ending on the same board.
+pub fn check_playback<P, T>(puzzle: P, rng: &mut impl Rng)
+where
+    P: Recordable<T> + Debug,
+    T: Piece + Debug,
+{
+    let (width, height) = puzzle.shape();
+    let mut recorder = Recorder::new(puzzle);
+
+    for _ in 0..SLIDES {
+        recorder.slide_from((rng.gen_range(0..width), rng.gen_range(0..height)));
+        let direction = Direction::ALL[rng.gen_range(0..4)];
+        recorder.slide_towards(direction, rng.gen_range(0..=width.max(height)));
+    }
+
+    let replay = recorder.replay();
+    let mut player = Player::new(replay).expect("the starting board does not play back");
+    while let Some(step) = player.step() {
+        if let Err(error) = step {
+            panic!("{error}: {replay:?}");
+        }
+    }
+
+    let pieces = recorder.iter().cloned().map(num::cast::<_, usize>);
+    assert!(
+        player.puzzle().iter().copied().map(Some).eq(pieces),
+        "{:?} differs from the recording {recorder:?}",
+        player.puzzle()
+    );
+}
+
+fn empty_of<P: Puzzle<T>, T: Piece>(puzzle: &P) -> (usize, usize) {
+    puzzle
+        .index_of(T::zero())
+        .expect("potential BUG: could not find an empty piece")
+}
+
+fn solvability_of<P: Puzzle<T>, T: Piece>(puzzle: &P) -> bool {
+    let pieces = puzzle.iter().cloned().collect::<Vec<_>>();
+    is_solvable(&pieces, puzzle.shape().0)
+}
+
+fn assert_same<P, M, T>(puzzle: &P, reference: &M)
+where
+    P: Puzzle<T> + Debug,
+    M: Puzzle<T> + Debug,
+    T: Piece + Debug,
+{
+    assert_eq!(puzzle.shape(), reference.shape(), "{puzzle:?}");
+    assert!(
+        puzzle.iter().eq(reference.iter()),
+        "{puzzle:?} differs from the reference {reference:?}"
+    );
+}
+
+#[test]
+fn box_puzzle_conforms() {
+    for shape in [(4, 4), (3, 5), (5, 3), (2, 2)] {
+        run(shape, |p: BoxPuzzle<u8>| p);
+    }
+}
+
+#[test]
+fn stack_puzzle_conforms() {
+    use crate::StackPuzzle;
+
+    run((4, 4), |p: BoxPuzzle<u8>| {
+        StackPuzzle::<4, 4, _>::try_from(p).unwrap()
+    });
+    run((3, 5), |p: BoxPuzzle<u16>| {
+        StackPuzzle::<3, 5, _>::try_from(p).unwrap()
+    });
+    run((5, 3), |p: BoxPuzzle<u32>| {
+        StackPuzzle::<5, 3, _>::try_from(p).unwrap()
+    });
+}
+
+#[cfg(feature = "ndarray")]
+#[test]
+fn ndarray_puzzle_conforms() {
+    use crate::NdArrayPuzzle;
+
+    for shape in [(4, 4), (3, 5), (5, 3), (2, 2)] {
+        run(shape, NdArrayPuzzle::<u8>::from);
+    }
+}
+
+#[test]
+fn zobrist_conforms() {
+    use crate::{Zobrist, ZobristTable};
+
+    let table = ZobristTable::random((3, 5));
+    run((3, 5), |p: BoxPuzzle<u8>| Zobrist::new(p, &table));
+}
+
+#[test]
+fn wrappers_conform() {
+    use crate::{MultiBlankPuzzle, TorusPuzzle, WalledPuzzle, Zobrist, ZobristTable};
+
+    let mut rng = Xoshiro256StarStar::seed_from_u64(SEED);
+    for shape in [(4, 4), (3, 5), (2, 2)] {
+        let table = ZobristTable::random_with_rng(&mut rng, shape);
+        for _ in 0..BOARDS {
+            let torus = TorusPuzzle::<u8>::random_with_rng(&mut rng, shape);
+            let recorder = Recorder::new(torus.clone());
+            check_against_model(recorder, torus.clone(), &mut rng);
+            check_against_model(Zobrist::new(torus.clone(), &table), torus.clone(), &mut rng);
+            check_playback(torus, &mut rng);
+
+            let multi_blank = MultiBlankPuzzle::<u8>::random_with_rng(&mut rng, shape, 2);
+            let recorder = Recorder::new(multi_blank.clone());
+            check_against_model(recorder, multi_blank.clone(), &mut rng);
+            check_playback(multi_blank.clone(), &mut rng);
+            let zobrist = Zobrist::new(multi_blank.clone(), &table);
+            check_against_model(zobrist, multi_blank, &mut rng);
+
+            let walled = WalledPuzzle::<u8>::random_level_with_rng(&mut rng, shape, 1);
+            let recorder = Recorder::new(walled.clone());
+            check_against_model(recorder, walled.clone(), &mut rng);
+            check_playback(walled.clone(), &mut rng);
+            check_against_model(Zobrist::new(walled.clone(), &table), walled, &mut rng);
+        }
+    }
+}
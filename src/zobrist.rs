@@ -0,0 +1,245 @@
+use rand::Rng;
+
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, Index};
+
+use crate::{Direction, LegalMoves, Piece, Puzzle};
+
+/// Random keys for every (cell, piece) pair of a given shape.
+///
+/// The Zobrist hash of a puzzle is the XOR of the keys of all of its cells, so a slide only
+/// needs to look at the row and column it went along to update it. See [`Zobrist`].
+#[derive(Debug, Clone)]
+pub struct ZobristTable {
+    keys: Box<[u64]>,
+    width: usize,
+    height: usize,
+}
+
+impl ZobristTable {
+    pub fn random_with_rng(rng: &mut (impl Rng + ?Sized), (width, height): (usize, usize)) -> Self {
+        let len = width * height;
+        Self {
+            keys: (0..len * len).map(|_| rng.gen()).collect(),
+            width,
+            height,
+        }
+    }
+
+    pub fn random((width, height): (usize, usize)) -> Self {
+        Self::random_with_rng(&mut rand::thread_rng(), (width, height))
+    }
+
+    #[inline]
+    pub fn shape(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn key<T: Piece>(&self, (x, y): (usize, usize), piece: T) -> u64 {
+        let piece: usize = num::cast(piece).expect("could not cast piece to usize");
+        self.keys[(y * self.width + x) * self.width * self.height + piece]
+    }
+
+    /// Computes the hash of `puzzle` from scratch.
+    pub fn hash_of<T: Piece, P: Puzzle<T>>(&self, puzzle: &P) -> u64 {
+        assert_eq!(puzzle.shape(), self.shape(), "shape mismatch");
+
+        puzzle
+            .iter_indexed()
+            .fold(0, |hash, (idx, piece)| hash ^ self.key(idx, piece.clone()))
+    }
+}
+
+/// A puzzle that keeps its Zobrist hash up to date on every slide.
+///
+/// Slides go to the puzzle it wraps, so a board that wraps around or has several blanks is
+/// hashed just as well.
+///
+/// [`Hash`] only writes the Zobrist hash, which makes this a cheap key for visited-state
+/// tracking in a `HashSet` or `HashMap`.
+#[derive(Debug, Clone)]
+pub struct Zobrist<'a, P> {
+    puzzle: P,
+    table: &'a ZobristTable,
+    hash: u64,
+}
+
+impl<'a, P> Zobrist<'a, P> {
+    pub fn new<T: Piece>(puzzle: P, table: &'a ZobristTable) -> Self
+    where
+        P: Puzzle<T>,
+    {
+        Self {
+            hash: table.hash_of(&puzzle),
+            puzzle,
+            table,
+        }
+    }
+
+    #[inline]
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn into_inner(self) -> P {
+        self.puzzle
+    }
+
+    // the keys of the pieces in the row and the column through `(x, y)`, which every piece a
+    // slide from or towards there moves along
+    fn cross_hash<T: Piece>(&self, (x, y): (usize, usize)) -> u64
+    where
+        P: Puzzle<T>,
+    {
+        let (width, height) = self.puzzle.shape();
+        let row = (0..width).map(|column| (column, y));
+        let column = (0..height).filter(|&row| row != y).map(|row| (x, row));
+
+        row.chain(column).fold(0, |hash, position| {
+            hash ^ self.table.key(position, self.puzzle[position].clone())
+        })
+    }
+}
+
+impl<P> Deref for Zobrist<'_, P> {
+    type Target = P;
+    fn deref(&self) -> &Self::Target {
+        &self.puzzle
+    }
+}
+
+impl<I, P: Index<I>> Index<I> for Zobrist<'_, P> {
+    type Output = P::Output;
+    fn index(&self, idx: I) -> &Self::Output {
+        &self.puzzle[idx]
+    }
+}
+
+impl<T: Piece, P: Puzzle<T>> Puzzle<T> for Zobrist<'_, P> {
+    type Iter<'a>
+        = P::Iter<'a>
+    where
+        Self: 'a,
+        T: 'a;
+    fn iter(&self) -> Self::Iter<'_> {
+        self.puzzle.iter()
+    }
+
+    #[inline]
+    fn shape(&self) -> (usize, usize) {
+        self.puzzle.shape()
+    }
+
+    fn index_of(&self, value: T) -> Option<(usize, usize)> {
+        self.puzzle.index_of(value)
+    }
+
+    fn is_solved(&self) -> bool {
+        self.puzzle.is_solved()
+    }
+
+    fn legal_moves(&self) -> LegalMoves {
+        self.puzzle.legal_moves()
+    }
+
+    fn slide_from(&mut self, from: (usize, usize)) -> Option<usize> {
+        let (width, height) = self.shape();
+        if from.0 >= width || from.1 >= height {
+            return None;
+        }
+
+        let before = self.cross_hash(from);
+        let distance = self.puzzle.slide_from(from)?;
+        self.hash ^= before ^ self.cross_hash(from);
+        Some(distance)
+    }
+
+    fn slide_towards(&mut self, direction: Direction, distance: usize) -> Option<usize> {
+        let empty = self
+            .puzzle
+            .index_of(T::zero())
+            .expect("potential BUG: could not find an empty piece");
+
+        let before = self.cross_hash(empty);
+        let distance = self.puzzle.slide_towards(direction, distance)?;
+        self.hash ^= before ^ self.cross_hash(empty);
+        Some(distance)
+    }
+}
+
+impl<P: PartialEq> PartialEq for Zobrist<'_, P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.puzzle == other.puzzle
+    }
+}
+
+impl<P: Eq> Eq for Zobrist<'_, P> {}
+
+impl<P> Hash for Zobrist<'_, P> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        state.write_u64(self.hash);
+    }
+}
+
+#[test]
+fn incremental_hash_works() {
+    use crate::{BoxPuzzle, Direction::*, MultiBlankPuzzle, StackPuzzle, TorusPuzzle};
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::collections::HashSet;
+
+    let mut rng = Xoshiro256StarStar::seed_from_u64(0x6a75676f);
+    let table = ZobristTable::random_with_rng(&mut rng, (4, 4));
+
+    let mut puzzle = Zobrist::new(StackPuzzle::default(), &table);
+    let mut visited = HashSet::from([puzzle.clone()]);
+
+    for _ in 0..256 {
+        let from = (rng.gen_range(0..4), rng.gen_range(0..4));
+        puzzle.slide_from(from);
+        assert_eq!(puzzle.zobrist_hash(), table.hash_of(&*puzzle));
+        visited.insert(puzzle.clone());
+    }
+
+    // walking back and forth revisits the same states
+    for _ in 0..8 {
+        puzzle.slide_towards(Left, 1);
+        puzzle.slide_towards(Right, 1);
+    }
+    assert!(visited.contains(&puzzle));
+
+    let table = ZobristTable::random_with_rng(&mut rng, (5, 3));
+    let mut puzzle = Zobrist::new(BoxPuzzle::<u8>::random_with_rng(&mut rng, (5, 3)), &table);
+    for _ in 0..256 {
+        let from = (rng.gen_range(0..5), rng.gen_range(0..3));
+        puzzle.slide_from(from);
+        assert_eq!(puzzle.zobrist_hash(), table.hash_of(&*puzzle));
+    }
+
+    // slides that wrap around, and into any of several blanks
+    let table = ZobristTable::random_with_rng(&mut rng, (4, 3));
+    let mut torus = Zobrist::new(TorusPuzzle::<u8>::random_with_rng(&mut rng, (4, 3)), &table);
+    let mut multi_blank = Zobrist::new(
+        MultiBlankPuzzle::<u8>::random_with_rng(&mut rng, (4, 3), 3),
+        &table,
+    );
+    for _ in 0..256 {
+        let from = (rng.gen_range(0..4), rng.gen_range(0..3));
+        let (direction, distance) = (Direction::ALL[rng.gen_range(0..4)], rng.gen_range(0..4));
+
+        torus.slide_from(from);
+        torus.slide_towards(direction, distance);
+        assert_eq!(torus.zobrist_hash(), table.hash_of(&*torus));
+
+        multi_blank.slide_from(from);
+        multi_blank.slide_towards(direction, distance);
+        assert_eq!(multi_blank.zobrist_hash(), table.hash_of(&*multi_blank));
+    }
+
+    let table = ZobristTable::random_with_rng(&mut rng, (4, 4));
+    let boxed = Zobrist::new(BoxPuzzle::default(), &table);
+    assert_eq!(
+        boxed.zobrist_hash(),
+        Zobrist::new(StackPuzzle::default(), &table).zobrist_hash()
+    );
+}